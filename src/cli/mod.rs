@@ -9,6 +9,7 @@ use strum_macros::{Display, EnumString};
 pub enum LogSetting {
     Netstat,
     Platform,
+    PowerSupply,
     SerialPorts,
     SystemCpu,
     SystemDisk,
@@ -40,8 +41,28 @@ pub struct Arguments {
     #[structopt(long, default_value = "6030")]
     pub port: u16,
 
+    /// Token required in the "Authorization: Bearer <token>" header by endpoints that change the system.
+    /// Such endpoints are disabled if no token is provided.
+    #[structopt(long)]
+    pub auth_token: Option<String>,
+
+    /// Case insensitive patterns, in a comma-separated list, of environment variables and command line options
+    /// that have their values redacted from process information. "*" matches any sequence of characters,
+    /// and leading dashes of options are ignored, so "PASSWORD" also matches "--password".
+    #[structopt(long, default_value = "*TOKEN*,*PASSWORD*,*KEY*", use_delimiter = true)]
+    pub secret_patterns: Vec<String>,
+
+    /// Path of the Docker Engine API unix socket
+    #[structopt(long, default_value = "/var/run/docker.sock")]
+    pub docker_socket: String,
+
+    /// Sysctl keys, in a comma-separated list, that can be changed (e.g., "vm.swappiness,net.core").
+    /// A key also allows all the keys under it.
+    #[structopt(long, use_delimiter = true)]
+    pub sysctl_allowlist: Vec<String>,
+
     /// Set logging intervals for various services in a comma-separated list (e.g., "system-cpu=10,system-disk=30")
-    /// Valid keys are: netstat, platform, serial-ports, system-cpu, system-disk, system-info, system-memory, system-network, system-process, system-temperature, system-unix-time-seconds
+    /// Valid keys are: netstat, platform, power-supply, serial-ports, system-cpu, system-disk, system-info, system-memory, system-network, system-process, system-temperature, system-unix-time-seconds
     #[structopt(long, parse(try_from_str = parse_log_settings), default_value="")]
     pub log_settings: HashMap<LogSetting, u64>,
 }
@@ -54,8 +75,22 @@ pub fn args() -> Arc<Arguments> {
     ARGS.clone()
 }
 
+// The authentication token is redacted, since the command line is logged
 pub fn command_line_string() -> String {
-    std::env::args().collect::<Vec<String>>().join(" ")
+    let mut redact_next = false;
+    std::env::args()
+        .map(|arg| {
+            if std::mem::take(&mut redact_next) {
+                return "<redacted>".to_string();
+            }
+            if arg.starts_with("--auth-token=") {
+                return "--auth-token=<redacted>".to_string();
+            }
+            redact_next = arg == "--auth-token";
+            arg
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
 }
 
 fn parse_log_settings(s: &str) -> Result<HashMap<LogSetting, u64>> {
@@ -94,9 +129,13 @@ fn validate_interval(key: &LogSetting, val: u64) -> Result<()> {
         LogSetting::SystemUnixTimeSeconds if val < 1 => Err(anyhow!(
             "Interval for '{key:?}' must not be less than 1 second."
         )),
-        LogSetting::SystemTemperature | LogSetting::Platform if val < 5 => Err(anyhow!(
-            "Interval for '{key:?}' must not be less than 5 seconds."
-        )),
+        LogSetting::SystemTemperature | LogSetting::Platform | LogSetting::PowerSupply
+            if val < 5 =>
+        {
+            Err(anyhow!(
+                "Interval for '{key:?}' must not be less than 5 seconds."
+            ))
+        }
         LogSetting::SystemProcess
         | LogSetting::Netstat
         | LogSetting::SerialPorts
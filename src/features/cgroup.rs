@@ -0,0 +1,222 @@
+use cached::proc_macro::cached;
+use paperclip::actix::Apiv2Schema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tracing::*;
+
+use crate::features::sysfs::read_u64;
+
+const MOUNTS_PATH: &str = "/proc/self/mounts";
+
+#[derive(Clone, Debug, Default, Serialize, Apiv2Schema)]
+pub struct CpuStat {
+    usage_usec: u64,
+    user_usec: u64,
+    system_usec: u64,
+    /// Only available when the cpu controller is enabled
+    nr_periods: Option<u64>,
+    nr_throttled: Option<u64>,
+    throttled_usec: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Apiv2Schema)]
+pub struct MemoryEvents {
+    low: u64,
+    high: u64,
+    max: u64,
+    oom: u64,
+    oom_kill: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct IoStat {
+    /// Device numbers as "major:minor"
+    device: String,
+    read_B: u64,
+    written_B: u64,
+    read_operations: u64,
+    write_operations: u64,
+    discarded_B: u64,
+    discard_operations: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Cgroup {
+    /// Path relative to the cgroup v2 hierarchy root, like "/system.slice/docker-<id>.scope"
+    path: String,
+    container_id: Option<String>,
+    cpu_stat: Option<CpuStat>,
+    memory_current_B: Option<u64>,
+    /// Not available when there is no limit
+    memory_max_B: Option<u64>,
+    memory_events: Option<MemoryEvents>,
+    io_stat: Vec<IoStat>,
+    pids_current: Option<u64>,
+}
+
+// Parses files with "key value" lines, like cpu.stat and memory.events
+fn read_flat_keyed(path: &Path) -> Option<HashMap<String, u64>> {
+    let content = fs::read_to_string(path).ok()?;
+    Some(
+        content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let key = fields.next()?;
+                let value = fields.next()?.parse::<u64>().ok()?;
+                Some((key.to_string(), value))
+            })
+            .collect(),
+    )
+}
+
+fn cpu_stat(path: &Path) -> Option<CpuStat> {
+    let values = read_flat_keyed(&path.join("cpu.stat"))?;
+    let value = |key: &str| values.get(key).cloned();
+    Some(CpuStat {
+        usage_usec: value("usage_usec").unwrap_or_default(),
+        user_usec: value("user_usec").unwrap_or_default(),
+        system_usec: value("system_usec").unwrap_or_default(),
+        nr_periods: value("nr_periods"),
+        nr_throttled: value("nr_throttled"),
+        throttled_usec: value("throttled_usec"),
+    })
+}
+
+fn memory_events(path: &Path) -> Option<MemoryEvents> {
+    let values = read_flat_keyed(&path.join("memory.events"))?;
+    let value = |key: &str| values.get(key).cloned().unwrap_or_default();
+    Some(MemoryEvents {
+        low: value("low"),
+        high: value("high"),
+        max: value("max"),
+        oom: value("oom"),
+        oom_kill: value("oom_kill"),
+    })
+}
+
+// Parses io.stat, formatted as "8:0 rbytes=90112 wbytes=0 rios=3 wios=0 dbytes=0 dios=0"
+fn io_stat(path: &Path) -> Vec<IoStat> {
+    let content = fs::read_to_string(path.join("io.stat")).unwrap_or_default();
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = fields.next()?.to_string();
+            let values: HashMap<&str, u64> = fields
+                .filter_map(|field| {
+                    let (key, value) = field.split_once('=')?;
+                    Some((key, value.parse().ok()?))
+                })
+                .collect();
+            let value = |key: &str| values.get(key).cloned().unwrap_or_default();
+            Some(IoStat {
+                device,
+                read_B: value("rbytes"),
+                written_B: value("wbytes"),
+                read_operations: value("rios"),
+                write_operations: value("wios"),
+                discarded_B: value("dbytes"),
+                discard_operations: value("dios"),
+            })
+        })
+        .collect()
+}
+
+// The cgroup v2 hierarchy is usually in /sys/fs/cgroup, or /sys/fs/cgroup/unified in hybrid setups
+#[cached(time = 60)]
+fn hierarchy_root() -> Option<String> {
+    fs::read_to_string(MOUNTS_PATH)
+        .unwrap_or_default()
+        .lines()
+        .find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [_device, mount_point, "cgroup2", ..] => Some(mount_point.to_string()),
+                _ => None,
+            }
+        })
+}
+
+// Container runtimes name the cgroups with the container id, e.g:
+// "/docker/<id>", "/system.slice/docker-<id>.scope" or "/kubepods/.../cri-containerd-<id>.scope"
+pub fn container_id(cgroup_path: &str) -> Option<String> {
+    cgroup_path.rsplit('/').find_map(|component| {
+        let component = component.trim_end_matches(".scope");
+        let id = component.rsplit('-').next().unwrap_or(component);
+        (id.len() == 64 && id.chars().all(|c| c.is_ascii_hexdigit())).then(|| id.to_string())
+    })
+}
+
+// Provides the cgroup v2 path of a process, from the "0::<path>" line of /proc/<pid>/cgroup
+pub fn process_cgroup(pid: u32) -> Option<String> {
+    fs::read_to_string(format!("/proc/{pid}/cgroup"))
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(String::from)
+}
+
+fn cgroup(root: &Path, path: &Path) -> Cgroup {
+    let relative_path = format!(
+        "/{}",
+        path.strip_prefix(root).unwrap_or(path).to_string_lossy()
+    );
+    Cgroup {
+        container_id: container_id(&relative_path),
+        path: relative_path,
+        cpu_stat: cpu_stat(path),
+        memory_current_B: read_u64(&path.join("memory.current")),
+        memory_max_B: read_u64(&path.join("memory.max")),
+        memory_events: memory_events(path),
+        io_stat: io_stat(path),
+        pids_current: read_u64(&path.join("pids.current")),
+    }
+}
+
+fn walk(root: &Path, path: &Path, cgroups: &mut Vec<Cgroup>) {
+    cgroups.push(cgroup(root, path));
+
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(error) => {
+            warn!("Failed to read {path:?}: {error}");
+            return;
+        }
+    };
+    let mut children: Vec<_> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().map_or(false, |kind| kind.is_dir()))
+        .map(|entry| entry.path())
+        .collect();
+    children.sort();
+    for child in children {
+        walk(root, &child, cgroups);
+    }
+}
+
+// Provides the cgroup at path and all its descendants, or the whole hierarchy if no path is defined
+#[cached(time = 5, result = true)]
+pub fn cgroups(path: Option<String>) -> Result<Vec<Cgroup>, String> {
+    let root = hierarchy_root().ok_or("No cgroup v2 hierarchy is mounted.")?;
+    let root = Path::new(&root);
+
+    let start = match &path {
+        Some(path) => {
+            if path.split('/').any(|component| component == "..") {
+                return Err(format!("Invalid cgroup path: {path}"));
+            }
+            root.join(path.trim_start_matches('/'))
+        }
+        None => root.to_path_buf(),
+    };
+    if !start.is_dir() {
+        return Err(format!("Cgroup {} not found.", path.unwrap_or_default()));
+    }
+
+    let mut cgroups = vec![];
+    walk(root, &start, &mut cgroups);
+    Ok(cgroups)
+}
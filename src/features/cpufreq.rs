@@ -0,0 +1,200 @@
+use paperclip::actix::Apiv2Schema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tracing::*;
+
+use crate::features::sysfs::{indexed_directories, name, read_list, read_string, read_u64};
+
+const CPU_PATH: &str = "/sys/devices/system/cpu";
+const CPUFREQ_PATH: &str = "/sys/devices/system/cpu/cpufreq";
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct FrequencyTime {
+    frequency_kHz: u64,
+    /// Time spent in this frequency since boot
+    time_s: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct FrequencyPolicy {
+    /// Name of the policy, like "policy0"
+    name: String,
+    /// CPUs that share this policy
+    cpus: Vec<u32>,
+    current_kHz: Option<u64>,
+    /// Frequency limits set by the governor configuration
+    minimum_kHz: Option<u64>,
+    maximum_kHz: Option<u64>,
+    /// Frequency limits supported by the hardware
+    hardware_minimum_kHz: Option<u64>,
+    hardware_maximum_kHz: Option<u64>,
+    governor: Option<String>,
+    available_governors: Vec<String>,
+    available_frequencies_kHz: Vec<u64>,
+    /// Only available when the kernel has CONFIG_CPU_FREQ_STAT
+    time_in_state: Vec<FrequencyTime>,
+    transitions: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct ThermalThrottle {
+    cpu: u32,
+    core_throttle_count: Option<u64>,
+    core_throttle_max_time_ms: Option<u64>,
+    core_throttle_total_time_ms: Option<u64>,
+    package_throttle_count: Option<u64>,
+    package_throttle_max_time_ms: Option<u64>,
+    package_throttle_total_time_ms: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct CpuFrequency {
+    policies: Vec<FrequencyPolicy>,
+    /// Thermal throttling counters, only available on x86 CPUs
+    thermal_throttle: Vec<ThermalThrottle>,
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct FrequencyPolicyRequest {
+    /// One of the available governors
+    governor: Option<String>,
+    /// Minimum frequency, within the hardware limits
+    minimum_kHz: Option<u64>,
+    /// Maximum frequency, within the hardware limits
+    maximum_kHz: Option<u64>,
+}
+
+// stats/time_in_state is formatted as "<frequency in kHz> <time in 10ms units>" per line
+fn time_in_state(path: &Path) -> Vec<FrequencyTime> {
+    read_string(&path.join("stats/time_in_state"))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let frequency_kHz = fields.next()?.parse::<u64>().ok()?;
+            let time = fields.next()?.parse::<u64>().ok()?;
+            Some(FrequencyTime {
+                frequency_kHz,
+                time_s: time as f64 / 100.0,
+            })
+        })
+        .collect()
+}
+
+fn policy(path: &Path) -> FrequencyPolicy {
+    FrequencyPolicy {
+        name: name(path),
+        cpus: read_list(&path.join("affected_cpus")),
+        current_kHz: read_u64(&path.join("scaling_cur_freq")),
+        minimum_kHz: read_u64(&path.join("scaling_min_freq")),
+        maximum_kHz: read_u64(&path.join("scaling_max_freq")),
+        hardware_minimum_kHz: read_u64(&path.join("cpuinfo_min_freq")),
+        hardware_maximum_kHz: read_u64(&path.join("cpuinfo_max_freq")),
+        governor: read_string(&path.join("scaling_governor")),
+        available_governors: read_list(&path.join("scaling_available_governors")),
+        available_frequencies_kHz: read_list(&path.join("scaling_available_frequencies")),
+        time_in_state: time_in_state(path),
+        transitions: read_u64(&path.join("stats/total_trans")),
+    }
+}
+
+fn thermal_throttle(cpu: u32, path: &Path) -> Option<ThermalThrottle> {
+    let path = path.join("thermal_throttle");
+    if !path.is_dir() {
+        return None;
+    }
+
+    let value = |name: &str| read_u64(&path.join(name));
+    Some(ThermalThrottle {
+        cpu,
+        core_throttle_count: value("core_throttle_count"),
+        core_throttle_max_time_ms: value("core_throttle_max_time_ms"),
+        core_throttle_total_time_ms: value("core_throttle_total_time_ms"),
+        package_throttle_count: value("package_throttle_count"),
+        package_throttle_max_time_ms: value("package_throttle_max_time_ms"),
+        package_throttle_total_time_ms: value("package_throttle_total_time_ms"),
+    })
+}
+
+pub fn frequency() -> CpuFrequency {
+    CpuFrequency {
+        policies: indexed_directories(CPUFREQ_PATH, "policy")
+            .iter()
+            .map(|(_, path)| policy(path))
+            .collect(),
+        thermal_throttle: indexed_directories(CPU_PATH, "cpu")
+            .iter()
+            .filter_map(|(cpu, path)| thermal_throttle(*cpu, path))
+            .collect(),
+    }
+}
+
+fn write(path: &Path, value: &str) -> Result<(), String> {
+    fs::write(path, value).map_err(|error| format!("Failed to write {path:?}: {error}"))
+}
+
+pub fn set_policy(index: u32, request: &FrequencyPolicyRequest) -> Result<FrequencyPolicy, String> {
+    let path = Path::new(CPUFREQ_PATH).join(format!("policy{index}"));
+    if !path.is_dir() {
+        return Err(format!("Frequency policy {index} not found."));
+    }
+    let current = policy(&path);
+
+    if let Some(governor) = &request.governor {
+        if !current.available_governors.contains(governor) {
+            return Err(format!(
+                "Invalid governor '{governor}', available governors are: {}",
+                current.available_governors.join(", ")
+            ));
+        }
+    }
+
+    let minimum = request
+        .minimum_kHz
+        .or(current.minimum_kHz)
+        .unwrap_or_default();
+    let maximum = request
+        .maximum_kHz
+        .or(current.maximum_kHz)
+        .unwrap_or(u64::MAX);
+    if minimum > maximum {
+        return Err(format!(
+            "Minimum frequency {minimum} kHz is higher than the maximum {maximum} kHz."
+        ));
+    }
+    let hardware_minimum = current.hardware_minimum_kHz.unwrap_or_default();
+    let hardware_maximum = current.hardware_maximum_kHz.unwrap_or(u64::MAX);
+    for frequency in [request.minimum_kHz, request.maximum_kHz].iter().flatten() {
+        if !(hardware_minimum..=hardware_maximum).contains(frequency) {
+            return Err(format!(
+                "Invalid frequency {frequency} kHz, it must be between {hardware_minimum} and {hardware_maximum} kHz."
+            ));
+        }
+    }
+
+    if let Some(governor) = &request.governor {
+        write(&path.join("scaling_governor"), governor)?;
+    }
+
+    // The kernel refuses a minimum above the current maximum, and vice versa,
+    // so the order of the writes depends on the direction of the change
+    let write_minimum = || match request.minimum_kHz {
+        Some(minimum) => write(&path.join("scaling_min_freq"), &minimum.to_string()),
+        None => Ok(()),
+    };
+    let write_maximum = || match request.maximum_kHz {
+        Some(maximum) => write(&path.join("scaling_max_freq"), &maximum.to_string()),
+        None => Ok(()),
+    };
+    if minimum > current.maximum_kHz.unwrap_or(u64::MAX) {
+        write_maximum()?;
+        write_minimum()?;
+    } else {
+        write_minimum()?;
+        write_maximum()?;
+    }
+
+    info!("Frequency policy {index} changed: {request:?}");
+    Ok(policy(&path))
+}
@@ -0,0 +1,187 @@
+use cached::proc_macro::cached;
+use paperclip::actix::Apiv2Schema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tracing::*;
+
+const RESOLV_CONF_PATH: &str = "/etc/resolv.conf";
+const SYSTEMD_RESOLVED_PATH: &str = "/run/systemd/resolve/";
+const SYSTEMD_RESOLVED_UPSTREAM_PATH: &str = "/run/systemd/resolve/resolv.conf";
+const HOSTS_PATH: &str = "/etc/hosts";
+const STATIC_HOSTNAME_PATH: &str = "/etc/hostname";
+const TRANSIENT_HOSTNAME_PATH: &str = "/proc/sys/kernel/hostname";
+
+#[derive(Clone, Debug, Default, Serialize, Apiv2Schema)]
+pub struct Resolver {
+    /// Real path of the resolv.conf file, after following symlinks
+    path: String,
+    /// True if resolv.conf is managed by systemd-resolved
+    systemd_resolved: bool,
+    nameservers: Vec<String>,
+    search: Vec<String>,
+    options: Vec<String>,
+    /// Nameservers used by systemd-resolved when resolv.conf points to its local stub
+    upstream_nameservers: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct HostEntry {
+    address: String,
+    hostnames: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Hostname {
+    /// Hostname configured in /etc/hostname, used after boot
+    static_hostname: Option<String>,
+    /// Hostname currently used by the kernel
+    transient_hostname: String,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Dns {
+    resolver: Resolver,
+    hosts: Vec<HostEntry>,
+    hostname: Hostname,
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct HostnameRequest {
+    hostname: String,
+    /// Also write /etc/hostname, so the hostname survives a reboot (default: true)
+    persistent: Option<bool>,
+}
+
+pub fn dns() -> Dns {
+    Dns {
+        resolver: resolver(),
+        hosts: hosts(),
+        hostname: hostname(),
+    }
+}
+
+fn parse_resolv_conf(content: &str) -> Resolver {
+    let mut resolver = Resolver::default();
+    for line in content.lines() {
+        let line = line
+            .split(|c| c == '#' || c == ';')
+            .next()
+            .unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("nameserver") => resolver.nameservers.extend(fields.map(String::from)),
+            // "domain" is an obsolete form of "search" with a single entry
+            Some("search") | Some("domain") => {
+                resolver.search = fields.map(String::from).collect();
+            }
+            Some("options") => resolver.options.extend(fields.map(String::from)),
+            _ => {}
+        }
+    }
+    resolver
+}
+
+#[cached(time = 5)]
+pub fn resolver() -> Resolver {
+    let path = fs::canonicalize(RESOLV_CONF_PATH)
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| RESOLV_CONF_PATH.into());
+
+    let mut resolver = match fs::read_to_string(&path) {
+        Ok(content) => parse_resolv_conf(&content),
+        Err(error) => {
+            warn!("Failed to read {path}: {error}");
+            Resolver::default()
+        }
+    };
+
+    resolver.systemd_resolved = path.starts_with(SYSTEMD_RESOLVED_PATH);
+    if resolver.systemd_resolved && path != SYSTEMD_RESOLVED_UPSTREAM_PATH {
+        match fs::read_to_string(SYSTEMD_RESOLVED_UPSTREAM_PATH) {
+            Ok(content) => {
+                resolver.upstream_nameservers = parse_resolv_conf(&content).nameservers;
+            }
+            Err(error) => warn!("Failed to read {SYSTEMD_RESOLVED_UPSTREAM_PATH}: {error}"),
+        }
+    }
+
+    resolver.path = path;
+    resolver
+}
+
+#[cached(time = 5)]
+pub fn hosts() -> Vec<HostEntry> {
+    let content = match fs::read_to_string(HOSTS_PATH) {
+        Ok(content) => content,
+        Err(error) => {
+            warn!("Failed to read {HOSTS_PATH}: {error}");
+            return vec![];
+        }
+    };
+
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace();
+            let address = fields.next()?;
+            Some(HostEntry {
+                address: address.into(),
+                hostnames: fields.map(String::from).collect(),
+            })
+        })
+        .collect()
+}
+
+pub fn hostname() -> Hostname {
+    Hostname {
+        static_hostname: fs::read_to_string(STATIC_HOSTNAME_PATH)
+            .ok()
+            .map(|content| content.trim().to_string())
+            .filter(|content| !content.is_empty()),
+        transient_hostname: fs::read_to_string(TRANSIENT_HOSTNAME_PATH)
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    }
+}
+
+// Follows RFC 1123: dot separated labels of alphanumeric characters and hyphens
+fn validate_hostname(hostname: &str) -> Result<(), String> {
+    if hostname.is_empty() || hostname.len() > 64 {
+        return Err("Hostname must have between 1 and 64 characters.".into());
+    }
+
+    let valid_label = |label: &str| {
+        !label.is_empty()
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    if !hostname.split('.').all(valid_label) {
+        return Err(format!(
+            "Invalid hostname '{hostname}', only letters, digits, hyphens and dots are allowed."
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn set_hostname(request: &HostnameRequest) -> Result<Hostname, String> {
+    let hostname = request.hostname.trim();
+    validate_hostname(hostname)?;
+
+    fs::write(TRANSIENT_HOSTNAME_PATH, hostname)
+        .map_err(|error| format!("Failed to set transient hostname: {error}"))?;
+
+    if request.persistent.unwrap_or(true) {
+        fs::write(STATIC_HOSTNAME_PATH, format!("{hostname}\n"))
+            .map_err(|error| format!("Failed to write {STATIC_HOSTNAME_PATH}: {error}"))?;
+    }
+
+    info!("Hostname changed to: {hostname}");
+    Ok(self::hostname())
+}
@@ -0,0 +1,637 @@
+use paperclip::actix::Apiv2Schema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+use tracing::*;
+
+use crate::cli;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+// Inspecting needs a request per container, so a few of them are done in parallel
+const INSPECT_CONCURRENCY: usize = 4;
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Port {
+    ip: Option<String>,
+    private_port: u16,
+    public_port: Option<u16>,
+    protocol: String,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Container {
+    id: String,
+    name: String,
+    image: String,
+    /// Container state, like running, exited or restarting
+    state: String,
+    /// Human readable status, like "Up 2 hours"
+    status: String,
+    /// Health check status, not available for containers without a health check
+    health: Option<String>,
+    restart_count: u64,
+    ports: Vec<Port>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct ContainerStats {
+    id: String,
+    name: String,
+    /// CPU usage in percent, where 100% is a single CPU fully used
+    cpu_usage: f64,
+    /// Memory used, without the inactive page cache, like `docker stats`
+    memory_usage_B: u64,
+    memory_limit_B: u64,
+    pids: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct LogLine {
+    /// stdout or stderr, containers with a TTY only have stdout
+    stream: String,
+    line: String,
+}
+
+// Subset of the Docker Engine API responses, check https://docs.docker.com/engine/api/
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiPort {
+    #[serde(rename = "IP")]
+    ip: Option<String>,
+    private_port: u16,
+    public_port: Option<u16>,
+    #[serde(rename = "Type")]
+    protocol: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiContainer {
+    id: String,
+    names: Vec<String>,
+    image: String,
+    state: String,
+    status: String,
+    #[serde(default)]
+    ports: Vec<ApiPort>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiHealth {
+    status: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiState {
+    health: Option<ApiHealth>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiConfig {
+    tty: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiInspect {
+    restart_count: u64,
+    #[serde(default)]
+    state: ApiState,
+    #[serde(default)]
+    config: ApiConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ApiCpuUsage {
+    total_usage: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ApiCpuStats {
+    #[serde(default)]
+    cpu_usage: ApiCpuUsage,
+    system_cpu_usage: Option<u64>,
+    online_cpus: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ApiMemoryStats {
+    usage: Option<u64>,
+    limit: Option<u64>,
+    #[serde(default)]
+    stats: std::collections::HashMap<String, u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ApiPidsStats {
+    current: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiStats {
+    name: String,
+    id: String,
+    #[serde(default)]
+    cpu_stats: ApiCpuStats,
+    #[serde(default)]
+    precpu_stats: ApiCpuStats,
+    #[serde(default)]
+    memory_stats: ApiMemoryStats,
+    #[serde(default)]
+    pids_stats: ApiPidsStats,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+fn socket_path() -> String {
+    cli::args().as_ref().docker_socket.clone()
+}
+
+// Minimal HTTP/1.0 client, the connection is closed by the server after the response,
+// and the body is not chunked
+fn get(socket_path: &str, path: &str) -> Result<Vec<u8>, String> {
+    let mut stream = UnixStream::connect(socket_path)
+        .map_err(|error| format!("Failed to connect to Docker at {socket_path}: {error}"))?;
+    stream
+        .set_read_timeout(Some(TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(TIMEOUT)))
+        .map_err(|error| format!("Failed to configure Docker socket: {error}"))?;
+
+    stream
+        .write_all(format!("GET {path} HTTP/1.0\r\nHost: docker\r\n\r\n").as_bytes())
+        .map_err(|error| format!("Failed to send request to Docker: {error}"))?;
+
+    let mut response = vec![];
+    stream
+        .read_to_end(&mut response)
+        .map_err(|error| format!("Failed to read response from Docker: {error}"))?;
+
+    let header_end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or("Invalid response from Docker: missing header.")?;
+    let body = response.split_off(header_end + 4);
+    let header = String::from_utf8_lossy(&response);
+
+    // Status line is formatted as "HTTP/1.0 200 OK"
+    let status = header
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or("Invalid response from Docker: missing status.")?;
+
+    if !(200..300).contains(&status) {
+        let message = serde_json::from_slice::<ApiError>(&body)
+            .map(|error| error.message)
+            .unwrap_or_else(|_| String::from_utf8_lossy(&body).trim().to_string());
+        debug!("Docker request {path} failed with {status}: {message}");
+        return Err(format!(
+            "Docker request failed with status {status}: {message}"
+        ));
+    }
+
+    Ok(body)
+}
+
+fn get_json<T: DeserializeOwned>(socket_path: &str, path: &str) -> Result<T, String> {
+    let body = get(socket_path, path)?;
+    serde_json::from_slice(&body)
+        .map_err(|error| format!("Failed to parse Docker response for {path}: {error}"))
+}
+
+// Container ids and names are used in the request path
+fn validate_container(container: &str) -> Result<(), String> {
+    let valid = !container.is_empty()
+        && container
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-');
+    if !valid {
+        return Err(format!("Invalid container id or name: '{container}'"));
+    }
+    Ok(())
+}
+
+fn inspect(socket_path: &str, container: &str) -> Result<ApiInspect, String> {
+    get_json(socket_path, &format!("/containers/{container}/json"))
+}
+
+// Health and restart count are only available when inspecting each container
+fn inspect_all(socket_path: &str, containers: &[ApiContainer]) -> Vec<Option<ApiInspect>> {
+    containers
+        .chunks(INSPECT_CONCURRENCY)
+        .flat_map(|chunk| {
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = chunk
+                    .iter()
+                    .map(|container| {
+                        scope.spawn(move || {
+                            inspect(socket_path, &container.id)
+                                .map_err(|error| {
+                                    warn!("Failed to inspect container {}: {error}", container.id)
+                                })
+                                .ok()
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().ok().flatten())
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect()
+}
+
+pub fn containers() -> Result<Vec<Container>, String> {
+    let socket_path = socket_path();
+    let containers: Vec<ApiContainer> = get_json(&socket_path, "/containers/json?all=1")?;
+    let inspects = inspect_all(&socket_path, &containers);
+
+    Ok(containers
+        .into_iter()
+        .zip(inspects)
+        .map(|(container, inspect)| Container {
+            name: container
+                .names
+                .first()
+                .map(|name| name.trim_start_matches('/').to_string())
+                .unwrap_or_default(),
+            health: inspect
+                .as_ref()
+                .and_then(|inspect| inspect.state.health.as_ref())
+                .map(|health| health.status.clone()),
+            restart_count: inspect
+                .map(|inspect| inspect.restart_count)
+                .unwrap_or_default(),
+            id: container.id,
+            image: container.image,
+            state: container.state,
+            status: container.status,
+            ports: container
+                .ports
+                .into_iter()
+                .map(|port| Port {
+                    ip: port.ip,
+                    private_port: port.private_port,
+                    public_port: port.public_port,
+                    protocol: port.protocol,
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+// Docker waits for two samples to fill precpu_stats, so the request takes about a second
+pub fn stats(container: &str) -> Result<ContainerStats, String> {
+    container_stats(&socket_path(), container)
+}
+
+fn container_stats(socket_path: &str, container: &str) -> Result<ContainerStats, String> {
+    validate_container(container)?;
+    let stats: ApiStats = get_json(
+        socket_path,
+        &format!("/containers/{container}/stats?stream=false"),
+    )?;
+
+    let cpu_delta = stats
+        .cpu_stats
+        .cpu_usage
+        .total_usage
+        .saturating_sub(stats.precpu_stats.cpu_usage.total_usage);
+    let system_delta = stats
+        .cpu_stats
+        .system_cpu_usage
+        .unwrap_or_default()
+        .saturating_sub(stats.precpu_stats.system_cpu_usage.unwrap_or_default());
+    let cpu_usage = if system_delta > 0 {
+        let online_cpus = stats.cpu_stats.online_cpus.unwrap_or(1) as f64;
+        cpu_delta as f64 / system_delta as f64 * online_cpus * 100.0
+    } else {
+        0.0
+    };
+
+    // cgroup v2 reports inactive_file, while cgroup v1 reports total_inactive_file
+    let memory = &stats.memory_stats;
+    let inactive_file = memory
+        .stats
+        .get("inactive_file")
+        .or_else(|| memory.stats.get("total_inactive_file"))
+        .cloned()
+        .unwrap_or_default();
+
+    Ok(ContainerStats {
+        id: stats.id,
+        name: stats.name.trim_start_matches('/').to_string(),
+        cpu_usage,
+        memory_usage_B: memory
+            .usage
+            .unwrap_or_default()
+            .saturating_sub(inactive_file),
+        memory_limit_B: memory.limit.unwrap_or_default(),
+        pids: stats.pids_stats.current.unwrap_or_default(),
+    })
+}
+
+// Without a TTY, stdout and stderr are multiplexed in frames with a 8 bytes header:
+// [stream type, 0, 0, 0, size (4 bytes, big endian)], where stream type is 1 for stdout and 2 for stderr
+fn demultiplex(body: &[u8]) -> Vec<LogLine> {
+    let mut lines = vec![];
+    let mut remaining = body;
+    while remaining.len() >= 8 {
+        let stream = match remaining[0] {
+            2 => "stderr",
+            _ => "stdout",
+        };
+        let size = u32::from_be_bytes([remaining[4], remaining[5], remaining[6], remaining[7]]);
+        let end = (8 + size as usize).min(remaining.len());
+        let content = String::from_utf8_lossy(&remaining[8..end]);
+        lines.extend(content.lines().map(|line| LogLine {
+            stream: stream.into(),
+            line: line.into(),
+        }));
+        remaining = &remaining[end..];
+    }
+    lines
+}
+
+pub fn logs(container: &str, lines: usize) -> Result<Vec<LogLine>, String> {
+    validate_container(container)?;
+    let socket_path = socket_path();
+    let tty = inspect(&socket_path, container)?.config.tty;
+    let body = get(
+        &socket_path,
+        &format!("/containers/{container}/logs?stdout=1&stderr=1&tail={lines}"),
+    )?;
+
+    if tty {
+        return Ok(String::from_utf8_lossy(&body)
+            .lines()
+            .map(|line| LogLine {
+                stream: "stdout".into(),
+                line: line.into(),
+            })
+            .collect());
+    }
+
+    Ok(demultiplex(&body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread::JoinHandle;
+
+    static STUB_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    struct Stub {
+        path: PathBuf,
+        handle: Option<JoinHandle<Vec<String>>>,
+    }
+
+    impl Stub {
+        // Serves each response to a single connection, in order
+        fn new(responses: Vec<Vec<u8>>) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "linux2rest-docker-{}-{}.sock",
+                std::process::id(),
+                STUB_COUNTER.fetch_add(1, Ordering::SeqCst)
+            ));
+            let _ = std::fs::remove_file(&path);
+            let listener = UnixListener::bind(&path).unwrap();
+
+            let handle = std::thread::spawn(move || {
+                responses
+                    .into_iter()
+                    .map(|response| {
+                        let (mut stream, _) = listener.accept().unwrap();
+                        let mut request = vec![];
+                        let mut buffer = [0u8; 1024];
+                        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                            let size = stream.read(&mut buffer).unwrap();
+                            if size == 0 {
+                                break;
+                            }
+                            request.extend_from_slice(&buffer[..size]);
+                        }
+                        stream.write_all(&response).unwrap();
+                        String::from_utf8_lossy(&request).to_string()
+                    })
+                    .collect()
+            });
+
+            Self {
+                path,
+                handle: Some(handle),
+            }
+        }
+
+        fn socket_path(&self) -> String {
+            self.path.to_string_lossy().to_string()
+        }
+
+        // Provides the requests received by the stub
+        fn requests(mut self) -> Vec<String> {
+            self.handle.take().unwrap().join().unwrap()
+        }
+    }
+
+    impl Drop for Stub {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    fn response(status: &str, body: &str) -> Vec<u8> {
+        format!(
+            "HTTP/1.0 {status}\r\nContent-Type: application/json\r\nServer: Docker\r\n\r\n{body}"
+        )
+        .into_bytes()
+    }
+
+    fn frame(stream: u8, content: &str) -> Vec<u8> {
+        let mut frame = vec![stream, 0, 0, 0];
+        frame.extend_from_slice(&(content.len() as u32).to_be_bytes());
+        frame.extend_from_slice(content.as_bytes());
+        frame
+    }
+
+    #[test]
+    fn get_provides_body_of_successful_response() {
+        let stub = Stub::new(vec![response("200 OK", "{\"ok\": true}")]);
+        let body = get(&stub.socket_path(), "/containers/json?all=1").unwrap();
+        assert_eq!(body, b"{\"ok\": true}");
+
+        let requests = stub.requests();
+        assert!(requests[0].starts_with("GET /containers/json?all=1 HTTP/1.0\r\n"));
+    }
+
+    #[test]
+    fn get_provides_message_of_error_response() {
+        let stub = Stub::new(vec![response(
+            "404 Not Found",
+            "{\"message\": \"No such container: web\"}",
+        )]);
+        let error = get(&stub.socket_path(), "/containers/web/json").unwrap_err();
+        assert_eq!(
+            error,
+            "Docker request failed with status 404: No such container: web"
+        );
+    }
+
+    #[test]
+    fn get_provides_body_of_error_response_without_message() {
+        let stub = Stub::new(vec![response(
+            "500 Internal Server Error",
+            "page not found\n",
+        )]);
+        let error = get(&stub.socket_path(), "/_ping").unwrap_err();
+        assert_eq!(
+            error,
+            "Docker request failed with status 500: page not found"
+        );
+    }
+
+    #[test]
+    fn get_refuses_invalid_responses() {
+        let stub = Stub::new(vec![b"garbage".to_vec(), b"HTTP/1.0\r\n\r\n{}".to_vec()]);
+        let socket_path = stub.socket_path();
+        assert!(get(&socket_path, "/_ping")
+            .unwrap_err()
+            .contains("missing header"));
+        assert!(get(&socket_path, "/_ping")
+            .unwrap_err()
+            .contains("missing status"));
+    }
+
+    #[test]
+    fn get_fails_without_docker() {
+        let error = get("/nonexistent/docker.sock", "/_ping").unwrap_err();
+        assert!(error.starts_with("Failed to connect to Docker at /nonexistent/docker.sock"));
+    }
+
+    #[test]
+    fn demultiplex_splits_streams_and_lines() {
+        let mut body = frame(1, "first\nsecond\n");
+        body.extend(frame(2, "error\n"));
+        body.extend(frame(1, "third"));
+
+        let lines: Vec<(String, String)> = demultiplex(&body)
+            .into_iter()
+            .map(|line| (line.stream, line.line))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("stdout".to_string(), "first".to_string()),
+                ("stdout".to_string(), "second".to_string()),
+                ("stderr".to_string(), "error".to_string()),
+                ("stdout".to_string(), "third".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn demultiplex_handles_truncated_frames() {
+        let mut body = frame(2, "complete\n");
+        // Header announces more bytes than available
+        body.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 100]);
+        body.extend_from_slice(b"partial");
+
+        let lines: Vec<String> = demultiplex(&body)
+            .into_iter()
+            .map(|line| line.line)
+            .collect();
+        assert_eq!(lines, vec!["complete", "partial"]);
+    }
+
+    #[test]
+    fn demultiplex_ignores_incomplete_headers() {
+        let mut body = frame(1, "complete\n");
+        body.extend_from_slice(&[1, 0, 0]);
+
+        let lines: Vec<String> = demultiplex(&body)
+            .into_iter()
+            .map(|line| line.line)
+            .collect();
+        assert_eq!(lines, vec!["complete"]);
+    }
+
+    #[test]
+    fn stats_calculates_cpu_and_memory_usage() {
+        let stub = Stub::new(vec![response(
+            "200 OK",
+            r#"{
+                "name": "/web",
+                "id": "abc",
+                "cpu_stats": {"cpu_usage": {"total_usage": 400}, "system_cpu_usage": 2000, "online_cpus": 2},
+                "precpu_stats": {"cpu_usage": {"total_usage": 200}, "system_cpu_usage": 1000},
+                "memory_stats": {"usage": 1000, "limit": 4096, "stats": {"inactive_file": 300}},
+                "pids_stats": {"current": 5}
+            }"#,
+        )]);
+        let stats = container_stats(&stub.socket_path(), "web").unwrap();
+        assert_eq!(stats.name, "web");
+        assert_eq!(stats.id, "abc");
+        // 200 of 1000 system ticks, on 2 CPUs
+        assert!((stats.cpu_usage - 40.0).abs() < 1e-9);
+        assert_eq!(stats.memory_usage_B, 700);
+        assert_eq!(stats.memory_limit_B, 4096);
+        assert_eq!(stats.pids, 5);
+
+        let requests = stub.requests();
+        assert!(requests[0].starts_with("GET /containers/web/stats?stream=false "));
+    }
+
+    #[test]
+    fn stats_handles_cgroup_v1_and_missing_samples() {
+        let stub = Stub::new(vec![response(
+            "200 OK",
+            r#"{
+                "name": "/db",
+                "id": "def",
+                "cpu_stats": {"cpu_usage": {"total_usage": 400}, "system_cpu_usage": 2000},
+                "precpu_stats": {"cpu_usage": {"total_usage": 0}},
+                "memory_stats": {"usage": 200, "stats": {"total_inactive_file": 500}}
+            }"#,
+        )]);
+        let stats = container_stats(&stub.socket_path(), "db").unwrap();
+        // Single CPU assumed when online_cpus is missing
+        assert!((stats.cpu_usage - 20.0).abs() < 1e-9);
+        // Inactive file larger than the usage doesn't underflow
+        assert_eq!(stats.memory_usage_B, 0);
+        assert_eq!(stats.memory_limit_B, 0);
+        assert_eq!(stats.pids, 0);
+    }
+
+    #[test]
+    fn stats_without_system_delta_has_no_cpu_usage() {
+        let stub = Stub::new(vec![response(
+            "200 OK",
+            r#"{
+                "name": "/idle",
+                "id": "123",
+                "cpu_stats": {"cpu_usage": {"total_usage": 400}, "system_cpu_usage": 1000},
+                "precpu_stats": {"cpu_usage": {"total_usage": 400}, "system_cpu_usage": 1000}
+            }"#,
+        )]);
+        let stats = container_stats(&stub.socket_path(), "idle").unwrap();
+        assert_eq!(stats.cpu_usage, 0.0);
+    }
+
+    #[test]
+    fn stats_refuses_invalid_container_names() {
+        let error = container_stats("/nonexistent/docker.sock", "../images").unwrap_err();
+        assert_eq!(error, "Invalid container id or name: '../images'");
+    }
+}
@@ -0,0 +1,129 @@
+use cached::proc_macro::cached;
+use paperclip::actix::Apiv2Schema;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::features::sysfs::{indexed_directories, name, read_i64, read_string};
+
+const HWMON_PATH: &str = "/sys/class/hwmon";
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Sensor {
+    /// Sensor name in the chip, like "temp1", "in0" or "fan2"
+    name: String,
+    /// temperature, voltage, current, power, energy, fan or humidity
+    #[serde(rename = "type")]
+    sensor_type: String,
+    label: Option<String>,
+    value: Option<f64>,
+    /// °C, V, A, W, J, RPM or %
+    unit: String,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    critical: Option<f64>,
+    /// Only available when the chip reports alarms
+    alarm: Option<bool>,
+    minimum_alarm: Option<bool>,
+    maximum_alarm: Option<bool>,
+    critical_alarm: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Chip {
+    /// Name of the hwmon device, like "hwmon0"
+    path: String,
+    /// Driver name, like "ina219" or "coretemp"
+    name: String,
+    sensors: Vec<Sensor>,
+}
+
+// Sensor types and their values scale, check the kernel documentation:
+// https://www.kernel.org/doc/Documentation/hwmon/sysfs-interface
+const SENSOR_TYPES: &[(&str, &str, &str, f64)] = &[
+    ("temp", "temperature", "°C", 1000.0),
+    ("in", "voltage", "V", 1000.0),
+    ("curr", "current", "A", 1000.0),
+    ("power", "power", "W", 1_000_000.0),
+    ("energy", "energy", "J", 1_000_000.0),
+    ("fan", "fan", "RPM", 1.0),
+    ("humidity", "humidity", "%", 1000.0),
+];
+
+// Splits attribute names like "temp1_input" into ("temp", "temp1")
+fn sensor_name(file_name: &str) -> Option<(&'static str, String)> {
+    let (name, _attribute) = file_name.split_once('_')?;
+    SENSOR_TYPES.iter().find_map(|(prefix, ..)| {
+        let index = name.strip_prefix(prefix)?;
+        (!index.is_empty() && index.chars().all(|c| c.is_ascii_digit()))
+            .then(|| (*prefix, name.to_string()))
+    })
+}
+
+fn sensor(directory: &Path, prefix: &str, name: &str) -> Sensor {
+    let (_, sensor_type, unit, scale) = SENSOR_TYPES
+        .iter()
+        .find(|(sensor_prefix, ..)| *sensor_prefix == prefix)
+        .cloned()
+        .unwrap_or_default();
+    let attribute = |attribute: &str| directory.join(format!("{name}_{attribute}"));
+    let value = |attribute_name: &str| {
+        read_i64(&attribute(attribute_name)).map(|value| value as f64 / scale)
+    };
+    let flag = |attribute_name: &str| read_i64(&attribute(attribute_name)).map(|value| value != 0);
+
+    Sensor {
+        name: name.into(),
+        sensor_type: sensor_type.into(),
+        label: read_string(&attribute("label")),
+        // Some power sensors only provide an average value
+        value: value("input").or_else(|| value("average")),
+        unit: unit.into(),
+        minimum: value("min"),
+        maximum: value("max"),
+        critical: value("crit"),
+        alarm: flag("alarm"),
+        minimum_alarm: flag("min_alarm"),
+        maximum_alarm: flag("max_alarm"),
+        critical_alarm: flag("crit_alarm"),
+    }
+}
+
+fn chip(directory: &Path) -> Chip {
+    let mut names: Vec<(&'static str, String)> = fs::read_dir(directory)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| sensor_name(&entry.file_name().to_string_lossy()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Sort by type order and numerically by index, so "in10" comes after "in9"
+    let order = |(prefix, name): &(&str, String)| {
+        let type_order = SENSOR_TYPES
+            .iter()
+            .position(|(sensor_prefix, ..)| sensor_prefix == prefix);
+        let index = name[prefix.len()..].parse::<u32>().unwrap_or_default();
+        (type_order, index)
+    };
+    names.sort_by_key(order);
+    names.dedup();
+
+    Chip {
+        path: name(directory),
+        name: read_string(&directory.join("name")).unwrap_or_default(),
+        sensors: names
+            .iter()
+            .map(|(prefix, name)| sensor(directory, prefix, name))
+            .collect(),
+    }
+}
+
+#[cached(time = 1)]
+pub fn hwmon() -> Vec<Chip> {
+    indexed_directories(HWMON_PATH, "hwmon")
+        .iter()
+        .map(|(_, directory)| chip(directory))
+        .collect()
+}
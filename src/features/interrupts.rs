@@ -0,0 +1,305 @@
+use cached::proc_macro::cached;
+use paperclip::actix::Apiv2Schema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tracing::*;
+
+use crate::features::rates::{Counters, RateSampler, Rates};
+use crate::features::sysfs::{parse_cpu_list, read_or_warn, read_string};
+
+const INTERRUPTS_PATH: &str = "/proc/interrupts";
+const SOFTIRQS_PATH: &str = "/proc/softirqs";
+const IRQ_PATH: &str = "/proc/irq";
+const RATE_INTERVAL: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    static ref RATES: RateSampler = RateSampler::start(RATE_INTERVAL, counters);
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Interrupt {
+    /// IRQ number, or a name for architecture specific interrupts, like "LOC" or "NMI"
+    irq: String,
+    /// Interrupt controller, like "IO-APIC" or "GICv2", not available for architecture specific interrupts
+    controller: Option<String>,
+    /// Devices sharing this IRQ, like "xhci_hcd"
+    devices: Vec<String>,
+    /// Remaining description, as provided by the kernel
+    description: String,
+    /// Count per CPU since boot
+    counts: Vec<u64>,
+    total: u64,
+    /// Increase per second, per CPU, over the last second, not available in the first second after the first request
+    rates_per_second: Option<Vec<f64>>,
+    /// Total increase per second over the last second, not available in the first second after the first request
+    total_rate_per_second: Option<f64>,
+    /// CPUs allowed to handle this IRQ, only available for numbered IRQs
+    smp_affinity: Option<Vec<usize>>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Softirq {
+    /// Softirq name, like "NET_RX" or "TIMER"
+    name: String,
+    /// Count per CPU since boot
+    counts: Vec<u64>,
+    total: u64,
+    /// Increase per second, per CPU, over the last second, not available in the first second after the first request
+    rates_per_second: Option<Vec<f64>>,
+    /// Total increase per second over the last second, not available in the first second after the first request
+    total_rate_per_second: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Interrupts {
+    /// Online CPUs, in the same order of the counts, like "CPU0"
+    cpus: Vec<String>,
+    interrupts: Vec<Interrupt>,
+    softirqs: Vec<Softirq>,
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct AffinityRequest {
+    /// CPUs allowed to handle the IRQ
+    smp_affinity: Vec<usize>,
+}
+
+struct Line {
+    name: String,
+    counts: Vec<u64>,
+    description: String,
+}
+
+// The first line has the online CPUs, the following ones are formatted as
+// "<name>: <count per CPU> <description>", some lines like "ERR" have a single count
+fn parse_lines(content: &str) -> (Vec<String>, Vec<Line>) {
+    let mut lines = content.lines();
+    let cpus: Vec<String> = lines
+        .next()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+
+    let lines = lines
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            let mut rest = rest.trim_start();
+            let mut counts = vec![];
+            while counts.len() < cpus.len() {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                match rest[..end].parse::<u64>() {
+                    Ok(count) => counts.push(count),
+                    Err(_) => break,
+                }
+                rest = rest[end..].trim_start();
+            }
+            Some(Line {
+                name: name.trim().to_string(),
+                counts,
+                description: rest.trim_end().to_string(),
+            })
+        })
+        .collect();
+
+    (cpus, lines)
+}
+
+fn smp_affinity(irq: &str) -> Option<Vec<usize>> {
+    read_string(Path::new(&format!("{IRQ_PATH}/{irq}/smp_affinity_list")))
+        .map(|list| parse_cpu_list(&list))
+}
+
+// Splits the first word, returning it and the remaining text
+fn split_word(text: &str) -> (&str, &str) {
+    let text = text.trim_start();
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    (&text[..end], text[end..].trim_start())
+}
+
+// The kernel prints the controller name padded to 8 characters, followed by the hardware IRQ,
+// the trigger type and the devices, like "IO-APIC   5-edge      ACPI:Ged" on x86
+// or "GICv2  27 Level     arch_timer" on ARM. Longer controller names keep a single space,
+// like "IR-PCI-MSI 327680-edge      nvme0q0", and IRQs without devices end after the trigger type
+fn controller_and_devices(description: &str) -> (Option<String>, Vec<String>) {
+    let (controller, mut rest) = split_word(description);
+
+    let (hardware_irq, after) = split_word(rest);
+    if hardware_irq.starts_with(|c: char| c.is_ascii_digit()) {
+        rest = after;
+    }
+    let (trigger, after) = split_word(rest);
+    if trigger.eq_ignore_ascii_case("level") || trigger.eq_ignore_ascii_case("edge") {
+        rest = after;
+    }
+
+    let devices = rest
+        .trim_end()
+        .split(", ")
+        .filter(|device| !device.is_empty())
+        .map(String::from)
+        .collect();
+    (
+        Some(controller.to_string()).filter(|controller| !controller.is_empty()),
+        devices,
+    )
+}
+
+fn interrupt_key(name: &str) -> String {
+    format!("irq.{name}")
+}
+
+fn softirq_key(name: &str) -> String {
+    format!("softirq.{name}")
+}
+
+fn counters() -> Counters {
+    let (_, interrupt_lines) = parse_lines(&read_or_warn(INTERRUPTS_PATH));
+    let (_, softirq_lines) = parse_lines(&read_or_warn(SOFTIRQS_PATH));
+    interrupt_lines
+        .into_iter()
+        .map(|line| (interrupt_key(&line.name), line.counts))
+        .chain(
+            softirq_lines
+                .into_iter()
+                .map(|line| (softirq_key(&line.name), line.counts)),
+        )
+        .collect()
+}
+
+fn interrupt(line: Line, rates: &Rates) -> Interrupt {
+    let numbered = line.name.parse::<u32>().is_ok();
+    let (controller, devices) = if numbered {
+        controller_and_devices(&line.description)
+    } else {
+        (None, vec![])
+    };
+
+    let rates_per_second = rates.get(&interrupt_key(&line.name)).cloned();
+    Interrupt {
+        smp_affinity: numbered.then(|| smp_affinity(&line.name)).flatten(),
+        irq: line.name,
+        controller,
+        devices,
+        description: line.description,
+        total: line.counts.iter().sum(),
+        total_rate_per_second: rates_per_second.as_ref().map(|rates| rates.iter().sum()),
+        rates_per_second,
+        counts: line.counts,
+    }
+}
+
+fn softirq(line: Line, rates: &Rates) -> Softirq {
+    let rates_per_second = rates.get(&softirq_key(&line.name)).cloned();
+    Softirq {
+        name: line.name,
+        total: line.counts.iter().sum(),
+        total_rate_per_second: rates_per_second.as_ref().map(|rates| rates.iter().sum()),
+        rates_per_second,
+        counts: line.counts,
+    }
+}
+
+#[cached(time = 1)]
+pub fn interrupts() -> Interrupts {
+    let (cpus, interrupt_lines) = parse_lines(&read_or_warn(INTERRUPTS_PATH));
+    let (_, softirq_lines) = parse_lines(&read_or_warn(SOFTIRQS_PATH));
+    let rates = RATES.rates();
+
+    Interrupts {
+        cpus,
+        interrupts: interrupt_lines
+            .into_iter()
+            .map(|line| interrupt(line, &rates))
+            .collect(),
+        softirqs: softirq_lines
+            .into_iter()
+            .map(|line| softirq(line, &rates))
+            .collect(),
+    }
+}
+
+pub fn set_smp_affinity(irq: u32, request: &AffinityRequest) -> Result<Vec<usize>, String> {
+    let path = format!("{IRQ_PATH}/{irq}/smp_affinity_list");
+    if fs::metadata(&path).is_err() {
+        return Err(format!("IRQ {irq} not found."));
+    }
+    if request.smp_affinity.is_empty() {
+        return Err("At least one CPU must be provided in smp_affinity.".into());
+    }
+
+    let list = request
+        .smp_affinity
+        .iter()
+        .map(|cpu| cpu.to_string())
+        .collect::<Vec<String>>()
+        .join(",");
+    // The kernel refuses offline CPUs and IRQs that can't be moved, like the timer ones
+    fs::write(&path, &list)
+        .map_err(|error| format!("Failed to set IRQ {irq} affinity to {list}: {error}"))?;
+    info!("IRQ {irq} affinity changed to: {list}");
+
+    smp_affinity(&irq.to_string()).ok_or_else(|| format!("Failed to read {path}."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_controller_name() {
+        assert_eq!(
+            controller_and_devices("IO-APIC   5-edge      ACPI:Ged"),
+            (Some("IO-APIC".into()), vec!["ACPI:Ged".into()])
+        );
+    }
+
+    #[test]
+    fn long_controller_name() {
+        assert_eq!(
+            controller_and_devices("IR-PCI-MSI 327680-edge      nvme0q0"),
+            (Some("IR-PCI-MSI".into()), vec!["nvme0q0".into()])
+        );
+    }
+
+    #[test]
+    fn shared_irq_devices() {
+        assert_eq!(
+            controller_and_devices("IO-APIC  16-fasteoi   ehci_hcd:usb1, snd_hda_intel"),
+            (
+                Some("IO-APIC".into()),
+                vec!["ehci_hcd:usb1".into(), "snd_hda_intel".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn separate_trigger_type() {
+        assert_eq!(
+            controller_and_devices("GICv2  27 Level     arch_timer"),
+            (Some("GICv2".into()), vec!["arch_timer".into()])
+        );
+    }
+
+    #[test]
+    fn short_controller_without_devices() {
+        assert_eq!(
+            controller_and_devices("IO-APIC   5-edge"),
+            (Some("IO-APIC".into()), vec![])
+        );
+        assert_eq!(
+            controller_and_devices("GICv2  27 Level"),
+            (Some("GICv2".into()), vec![])
+        );
+    }
+
+    #[test]
+    fn irq_without_devices() {
+        assert_eq!(
+            controller_and_devices("IR-PCI-MSI 327680-edge"),
+            (Some("IR-PCI-MSI".into()), vec![])
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use cached::proc_macro::cached;
+use flate2::read::GzDecoder;
+use paperclip::actix::Apiv2Schema;
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use tracing::*;
+
+use crate::features::sysfs::read_or_warn;
+
+const CMDLINE_PATH: &str = "/proc/cmdline";
+const TAINTED_PATH: &str = "/proc/sys/kernel/tainted";
+const MODULES_PATH: &str = "/proc/modules";
+const CONFIG_PATH: &str = "/proc/config.gz";
+
+// Taint flags by bit, check https://www.kernel.org/doc/html/latest/admin-guide/tainted-kernels.html
+const TAINT_FLAGS: &[(char, &str)] = &[
+    ('P', "Proprietary module was loaded"),
+    ('F', "Module was force loaded"),
+    ('S', "Kernel running on an out of specification system"),
+    ('R', "Module was force unloaded"),
+    ('M', "Processor reported a Machine Check Exception"),
+    ('B', "Bad page referenced or some unexpected page flags"),
+    ('U', "Taint requested by userspace application"),
+    ('D', "Kernel died recently, i.e. there was an OOPS or BUG"),
+    ('A', "ACPI table overridden by user"),
+    ('W', "Kernel issued warning"),
+    ('C', "Staging driver was loaded"),
+    ('I', "Workaround for bug in platform firmware applied"),
+    ('O', "Externally-built (out-of-tree) module was loaded"),
+    ('E', "Unsigned module was loaded"),
+    ('L', "Soft lockup occurred"),
+    ('K', "Kernel has been live patched"),
+    ('X', "Auxiliary taint, defined for and used by distros"),
+    ('T', "Kernel was built with the struct randomization plugin"),
+    ('N', "An in-kernel test has been run"),
+];
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct TaintFlag {
+    bit: u32,
+    letter: String,
+    description: String,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Taint {
+    /// Zero when the kernel is not tainted
+    value: u64,
+    flags: Vec<TaintFlag>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Module {
+    name: String,
+    size_B: u64,
+    /// Number of modules and processes using this module
+    reference_count: u64,
+    /// Modules that depend on this module
+    used_by: Vec<String>,
+    /// Live, Loading or Unloading
+    state: String,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct ConfigOption {
+    /// Option name, like "CONFIG_USB_VIDEO_CLASS"
+    name: String,
+    /// y, m, a number or a string, options that are not set have the value "n"
+    value: String,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct KernelInfo {
+    cmdline: String,
+    taint: Taint,
+    modules: Vec<Module>,
+    /// Only available when the kernel was built with CONFIG_IKCONFIG_PROC
+    config: Option<Vec<ConfigOption>>,
+}
+
+pub fn taint() -> Taint {
+    let value = read_or_warn(TAINTED_PATH)
+        .trim()
+        .parse::<u64>()
+        .unwrap_or_default();
+    Taint {
+        value,
+        flags: (0..64)
+            .filter(|bit| value & (1 << bit) != 0)
+            .map(|bit| {
+                let (letter, description) = TAINT_FLAGS
+                    .get(bit as usize)
+                    .cloned()
+                    .unwrap_or(('?', "Unknown"));
+                TaintFlag {
+                    bit,
+                    letter: letter.to_string(),
+                    description: description.into(),
+                }
+            })
+            .collect(),
+    }
+}
+
+// Lines are formatted as "<name> <size> <reference count> <used by> <state> <address>",
+// where used by is a comma terminated list or "-", e.g: "snd 106496 1 snd_pcm, Live 0x0000000000000000"
+pub fn modules() -> Vec<Module> {
+    read_or_warn(MODULES_PATH)
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [name, size, reference_count, used_by, state, ..] => Some(Module {
+                    name: name.to_string(),
+                    size_B: size.parse().unwrap_or_default(),
+                    reference_count: reference_count.parse().unwrap_or_default(),
+                    used_by: used_by
+                        .split(',')
+                        .filter(|module| !module.is_empty() && *module != "-")
+                        .map(String::from)
+                        .collect(),
+                    state: state.to_string(),
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn parse_config(content: &str) -> Vec<ConfigOption> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if let Some(name) = line
+                .strip_prefix("# ")
+                .and_then(|line| line.strip_suffix(" is not set"))
+            {
+                return Some(ConfigOption {
+                    name: name.into(),
+                    value: "n".into(),
+                });
+            }
+            if line.starts_with('#') {
+                return None;
+            }
+            let (name, value) = line.split_once('=')?;
+            Some(ConfigOption {
+                name: name.into(),
+                value: value.trim_matches('"').into(),
+            })
+        })
+        .collect()
+}
+
+#[cached(time = 60)]
+pub fn config() -> Option<Vec<ConfigOption>> {
+    let file = fs::File::open(CONFIG_PATH).ok()?;
+    let mut content = String::new();
+    if let Err(error) = GzDecoder::new(file).read_to_string(&mut content) {
+        warn!("Failed to decompress {CONFIG_PATH}: {error}");
+        return None;
+    }
+    Some(parse_config(&content))
+}
+
+pub fn kernel_info() -> KernelInfo {
+    KernelInfo {
+        cmdline: read_or_warn(CMDLINE_PATH).trim().to_string(),
+        taint: taint(),
+        modules: modules(),
+        config: config(),
+    }
+}
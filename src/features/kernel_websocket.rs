@@ -1,4 +1,4 @@
-use crate::features::kernel;
+use crate::features::{kernel, power_supply};
 use actix::prelude::*;
 use actix::{self, Actor, Addr, AsyncContext, Handler, Message, StreamHandler};
 use actix_web_actors::ws;
@@ -12,6 +12,7 @@ use std::sync::{Arc, Mutex};
 #[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
 pub enum WebsocketEventType {
     KernelBuffer,
+    PowerSupply,
 }
 
 pub struct StringMessage(String);
@@ -44,8 +45,12 @@ pub fn manager() -> Arc<Mutex<WebsocketManager>> {
     return SYSTEM.clone();
 }
 
-pub fn new_websocket(_event_type: WebsocketEventType) -> WebsocketActor {
-    WebsocketActor::new(SYSTEM.clone())
+pub fn new_websocket(event_type: WebsocketEventType) -> WebsocketActor {
+    let receiver = match event_type {
+        WebsocketEventType::KernelBuffer => kernel::ask_for_client(),
+        WebsocketEventType::PowerSupply => power_supply::ask_for_client(),
+    };
+    WebsocketActor::new(SYSTEM.clone(), receiver)
 }
 
 pub struct WebsocketActor {
@@ -54,10 +59,10 @@ pub struct WebsocketActor {
 }
 
 impl WebsocketActor {
-    pub fn new(server: Arc<Mutex<WebsocketManager>>) -> Self {
+    pub fn new(server: Arc<Mutex<WebsocketManager>>, receiver: Receiver<String>) -> Self {
         Self {
             server,
-            receiver: Some(kernel::ask_for_client()),
+            receiver: Some(receiver),
         }
     }
 }
@@ -1,8 +1,23 @@
+pub mod cgroup;
+pub mod cpufreq;
+pub mod dns;
+pub mod docker;
+pub mod hwmon;
+pub mod interrupts;
 pub mod kernel;
+pub mod kernel_info;
 pub mod kernel_websocket;
 pub mod model;
 pub mod netstat;
 pub mod platform;
+pub mod power_supply;
+pub mod process;
+pub mod rates;
 pub mod serial;
+pub mod sysctl;
+pub mod sysfs;
 pub mod system;
+pub mod thermal;
+pub mod time;
 pub mod udev;
+pub mod users;
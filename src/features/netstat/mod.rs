@@ -0,0 +1,434 @@
+pub mod stats;
+pub mod unix;
+
+use netstat2::{AddressFamilyFlags, ProtocolFlags, ProtocolSocketInfo, TcpState};
+use paperclip::actix::Apiv2Schema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::sync::Arc;
+use tracing::*;
+
+use crate::features::system::{self, ProcessSummary};
+
+lazy_static! {
+    static ref SERVICES: Arc<HashMap<(Protocol, u16), String>> = Arc::new(services());
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Apiv2Schema)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressFamily {
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressFamily {
+    fn from(address: &IpAddr) -> Self {
+        match address {
+            IpAddr::V4(_) => AddressFamily::Ipv4,
+            IpAddr::V6(_) => AddressFamily::Ipv6,
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash, Apiv2Schema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Tcp,
+    Udp,
+    Unix,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Apiv2Schema)]
+pub struct Filter {
+    /// Restricts the sockets to a single address family, ipv4 or ipv6, unix sockets are excluded when set
+    pub address_family: Option<AddressFamily>,
+    /// Restricts the sockets to a single protocol, tcp, udp or unix, all are provided by default
+    pub protocol: Option<Protocol>,
+    /// TCP or unix socket state, like LISTEN, ESTABLISHED or CONNECTED, UDP sockets are excluded when set.
+    /// LISTEN and LISTENING are the same, so both TCP and unix listening sockets are provided
+    pub state: Option<String>,
+    /// Unix sockets are excluded when set
+    pub local_port: Option<u16>,
+    /// UDP and unix sockets are excluded when set
+    pub remote_port: Option<u16>,
+    /// Local or remote IP address, unix sockets are excluded when set, invalid addresses are refused
+    #[serde(default, deserialize_with = "deserialize_address")]
+    pub address: Option<String>,
+    pub pid: Option<u32>,
+    /// Embeds name, executable, user and command line of the processes using each socket
+    pub processes: Option<bool>,
+}
+
+fn deserialize_address<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let address = Option::<String>::deserialize(deserializer)?;
+    if let Some(address) = &address {
+        if let Err(error) = address.trim().parse::<IpAddr>() {
+            return Err(serde::de::Error::custom(format!(
+                "invalid address '{address}': {error}"
+            )));
+        }
+    }
+    Ok(address)
+}
+
+// TCP sockets use LISTEN while unix sockets use LISTENING
+fn normalized_state(state: &str) -> String {
+    let state = state.trim().to_uppercase();
+    if state == "LISTENING" {
+        return "LISTEN".into();
+    }
+    state
+}
+
+impl Filter {
+    fn matches_state(&self, state: &str) -> bool {
+        self.state.as_ref().map_or(true, |filter| {
+            normalized_state(filter) == normalized_state(state)
+        })
+    }
+
+    fn protocol_flags(&self) -> ProtocolFlags {
+        match self.protocol {
+            Some(Protocol::Tcp) => ProtocolFlags::TCP,
+            Some(Protocol::Udp) => ProtocolFlags::UDP,
+            Some(Protocol::Unix) => ProtocolFlags::empty(),
+            None => ProtocolFlags::TCP | ProtocolFlags::UDP,
+        }
+    }
+
+    fn address_family_flags(&self) -> AddressFamilyFlags {
+        match self.address_family {
+            Some(AddressFamily::Ipv4) => AddressFamilyFlags::IPV4,
+            Some(AddressFamily::Ipv6) => AddressFamilyFlags::IPV6,
+            None => AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6,
+        }
+    }
+
+    fn matches(&self, socket_info: &netstat2::SocketInfo) -> bool {
+        if let Some(pid) = self.pid {
+            if !socket_info.associated_pids.contains(&pid) {
+                return false;
+            }
+        }
+
+        // Addresses are validated when the filter is deserialized
+        let address = self
+            .address
+            .as_ref()
+            .and_then(|address| address.trim().parse::<IpAddr>().ok());
+
+        match &socket_info.protocol_socket_info {
+            ProtocolSocketInfo::Tcp(tcp) => {
+                self.matches_state(&tcp.state.to_string())
+                    && self.local_port.map_or(true, |port| tcp.local_port == port)
+                    && self
+                        .remote_port
+                        .map_or(true, |port| tcp.remote_port == port)
+                    && address.map_or(true, |address| {
+                        tcp.local_addr == address || tcp.remote_addr == address
+                    })
+            }
+            ProtocolSocketInfo::Udp(udp) => {
+                self.state.is_none()
+                    && self.remote_port.is_none()
+                    && self.local_port.map_or(true, |port| udp.local_port == port)
+                    && address.map_or(true, |address| udp.local_addr == address)
+            }
+        }
+    }
+
+    fn matches_unix(&self, unix: &unix::Unix) -> bool {
+        self.protocol
+            .map_or(true, |protocol| protocol == Protocol::Unix)
+            && self.address_family.is_none()
+            && self.local_port.is_none()
+            && self.remote_port.is_none()
+            && self.address.is_none()
+            && self.matches_state(&unix.state)
+            && self.pid.map_or(true, |pid| unix.pids.contains(&pid))
+    }
+}
+
+#[derive(Debug, Serialize, Apiv2Schema)]
+pub struct ListeningService {
+    protocol: Protocol,
+    port: u16,
+    /// Service name from /etc/services
+    service: Option<String>,
+    addresses: Vec<String>,
+    pids: Vec<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    processes: Option<Vec<ProcessSummary>>,
+}
+
+#[derive(Debug, Serialize, Apiv2Schema)]
+struct AddressPort {
+    address: String,
+    port: u16,
+}
+
+#[derive(Debug, Serialize, Apiv2Schema)]
+struct Udp {
+    address_family: AddressFamily,
+    local: AddressPort,
+    pids: Vec<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    processes: Option<Vec<ProcessSummary>>,
+}
+
+impl Udp {
+    fn new(udp: &netstat2::UdpSocketInfo, socket_info: &netstat2::SocketInfo) -> Udp {
+        Udp {
+            address_family: AddressFamily::from(&udp.local_addr),
+            local: AddressPort {
+                address: udp.local_addr.to_string(),
+                port: udp.local_port,
+            },
+            pids: socket_info.associated_pids.clone(),
+            processes: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Apiv2Schema)]
+struct Tcp {
+    address_family: AddressFamily,
+    local: AddressPort,
+    remote: AddressPort,
+    pids: Vec<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    processes: Option<Vec<ProcessSummary>>,
+    state: String,
+}
+
+impl Tcp {
+    fn new(tcp: &netstat2::TcpSocketInfo, socket_info: &netstat2::SocketInfo) -> Tcp {
+        Tcp {
+            address_family: AddressFamily::from(&tcp.local_addr),
+            local: AddressPort {
+                address: tcp.local_addr.to_string(),
+                port: tcp.local_port,
+            },
+            remote: AddressPort {
+                address: tcp.remote_addr.to_string(),
+                port: tcp.remote_port,
+            },
+            pids: socket_info.associated_pids.clone(),
+            processes: None,
+            state: format!("{}", tcp.state),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Apiv2Schema)]
+pub struct Netstat {
+    tcp: Vec<Tcp>,
+    udp: Vec<Udp>,
+    unix: Vec<unix::Unix>,
+}
+
+fn summaries(processes: &HashMap<u32, ProcessSummary>, pids: &[u32]) -> Vec<ProcessSummary> {
+    pids.iter()
+        .filter_map(|pid| processes.get(pid).cloned())
+        .collect()
+}
+
+impl Netstat {
+    // Fill the process details of every socket, looking all pids up at once
+    fn embed_processes(&mut self) {
+        let pids: Vec<u32> = self
+            .tcp
+            .iter()
+            .flat_map(|tcp| tcp.pids.iter())
+            .chain(self.udp.iter().flat_map(|udp| udp.pids.iter()))
+            .chain(self.unix.iter().flat_map(|unix| unix.pids.iter()))
+            .cloned()
+            .collect();
+        let processes = system::process_summaries(&pids);
+
+        for tcp in self.tcp.iter_mut() {
+            tcp.processes = Some(summaries(&processes, &tcp.pids));
+        }
+        for udp in self.udp.iter_mut() {
+            udp.processes = Some(summaries(&processes, &udp.pids));
+        }
+        for unix in self.unix.iter_mut() {
+            unix.processes = Some(summaries(&processes, &unix.pids));
+        }
+    }
+}
+
+fn sockets_info(filter: &Filter) -> Vec<netstat2::SocketInfo> {
+    if filter.protocol == Some(Protocol::Unix) {
+        return vec![];
+    }
+
+    let sockets_info =
+        netstat2::get_sockets_info(filter.address_family_flags(), filter.protocol_flags()).unwrap();
+
+    sockets_info
+        .into_iter()
+        .filter(|socket_info| filter.matches(socket_info))
+        .collect()
+}
+
+pub fn netstat(filter: &Filter) -> Netstat {
+    let sockets_info_iter = sockets_info(filter);
+
+    let tcps = sockets_info_iter
+        .iter()
+        .filter_map(|socket_info| match &socket_info.protocol_socket_info {
+            ProtocolSocketInfo::Tcp(tcp) => Some((socket_info.clone(), tcp)),
+            ProtocolSocketInfo::Udp(_udp) => None,
+        })
+        .map(|(socket_info, tcp)| Tcp::new(tcp, &socket_info))
+        .collect::<Vec<Tcp>>();
+
+    let udps = sockets_info_iter
+        .iter()
+        .filter_map(|socket_info| match &socket_info.protocol_socket_info {
+            ProtocolSocketInfo::Tcp(_tcp) => None,
+            ProtocolSocketInfo::Udp(udp) => Some((socket_info.clone(), udp)),
+        })
+        .map(|(socket_info, udp)| Udp::new(udp, &socket_info))
+        .collect::<Vec<Udp>>();
+
+    let mut netstat = Netstat {
+        tcp: tcps,
+        udp: udps,
+        unix: unix_sockets(filter),
+    };
+
+    if filter.processes.unwrap_or(false) {
+        netstat.embed_processes();
+    }
+
+    netstat
+}
+
+fn unix_sockets(filter: &Filter) -> Vec<unix::Unix> {
+    let mut sockets = unix::sockets();
+    sockets.retain(|unix| filter.matches_unix(unix));
+    sockets
+}
+
+// Provides the unix sockets only, with the same filters used by netstat
+pub fn unix(filter: &Filter) -> Vec<unix::Unix> {
+    let mut netstat = Netstat {
+        tcp: vec![],
+        udp: vec![],
+        unix: unix_sockets(filter),
+    };
+
+    if filter.processes.unwrap_or(false) {
+        netstat.embed_processes();
+    }
+
+    netstat.unix
+}
+
+// Provides the sockets bound to a local port, with the processes that own them
+pub fn port(port: u16) -> Netstat {
+    netstat(&Filter {
+        local_port: Some(port),
+        processes: Some(true),
+        ..Default::default()
+    })
+}
+
+// Provides TCP sockets in LISTEN state and bound UDP sockets, grouped by protocol and port
+pub fn listening(processes: bool) -> Vec<ListeningService> {
+    let mut services: BTreeMap<(Protocol, u16), ListeningService> = BTreeMap::new();
+
+    for socket_info in sockets_info(&Filter::default()) {
+        let (protocol, address, port) = match &socket_info.protocol_socket_info {
+            ProtocolSocketInfo::Tcp(tcp) if tcp.state == TcpState::Listen => {
+                (Protocol::Tcp, tcp.local_addr, tcp.local_port)
+            }
+            ProtocolSocketInfo::Tcp(_tcp) => continue,
+            ProtocolSocketInfo::Udp(udp) => (Protocol::Udp, udp.local_addr, udp.local_port),
+        };
+
+        let service = services
+            .entry((protocol, port))
+            .or_insert_with(|| ListeningService {
+                protocol,
+                port,
+                service: SERVICES.get(&(protocol, port)).cloned(),
+                addresses: vec![],
+                pids: vec![],
+                processes: None,
+            });
+
+        let address = address.to_string();
+        if !service.addresses.contains(&address) {
+            service.addresses.push(address);
+        }
+        for pid in socket_info.associated_pids {
+            if !service.pids.contains(&pid) {
+                service.pids.push(pid);
+            }
+        }
+    }
+
+    let mut services: Vec<ListeningService> = services.into_values().collect();
+
+    if processes {
+        let pids: Vec<u32> = services
+            .iter()
+            .flat_map(|service| service.pids.iter())
+            .cloned()
+            .collect();
+        let processes = system::process_summaries(&pids);
+        for service in services.iter_mut() {
+            service.processes = Some(summaries(&processes, &service.pids));
+        }
+    }
+
+    services
+}
+
+// Parses /etc/services, lines are formatted as: "name port/protocol [aliases...] [# comment]"
+fn services() -> HashMap<(Protocol, u16), String> {
+    let content = match std::fs::read_to_string("/etc/services") {
+        Ok(content) => content,
+        Err(error) => {
+            warn!("Failed to read /etc/services: {error}");
+            return HashMap::new();
+        }
+    };
+
+    let mut services = HashMap::new();
+    for line in content.lines() {
+        let mut fields = line
+            .split('#')
+            .next()
+            .unwrap_or_default()
+            .split_whitespace();
+        let (name, port, protocol) = match (
+            fields.next(),
+            fields.next().and_then(|field| field.split_once('/')),
+        ) {
+            (Some(name), Some((port, protocol))) => (name, port, protocol),
+            _ => continue,
+        };
+        let protocol = match protocol {
+            "tcp" => Protocol::Tcp,
+            "udp" => Protocol::Udp,
+            _ => continue,
+        };
+        if let Ok(port) = port.parse::<u16>() {
+            services
+                .entry((protocol, port))
+                .or_insert_with(|| name.to_string());
+        }
+    }
+
+    services
+}
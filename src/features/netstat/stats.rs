@@ -0,0 +1,216 @@
+use cached::proc_macro::cached;
+use paperclip::actix::Apiv2Schema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::features::rates::{Counters, RateSampler, Rates};
+use crate::features::sysfs::read_or_warn;
+
+const RATE_INTERVAL: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    static ref RATES: RateSampler = RateSampler::start(RATE_INTERVAL, counters);
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Counter {
+    value: u64,
+    /// Increase per second over the last second, not available in the first second after the first request
+    rate_per_second: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct TcpStats {
+    current_established: u64,
+    active_opens: Counter,
+    passive_opens: Counter,
+    attempt_fails: Counter,
+    established_resets: Counter,
+    in_segments: Counter,
+    out_segments: Counter,
+    retransmitted_segments: Counter,
+    in_errors: Counter,
+    out_resets: Counter,
+    timeouts: Counter,
+    listen_overflows: Counter,
+    listen_drops: Counter,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct UdpStats {
+    in_datagrams: Counter,
+    out_datagrams: Counter,
+    no_ports: Counter,
+    in_errors: Counter,
+    receive_buffer_errors: Counter,
+    send_buffer_errors: Counter,
+    in_checksum_errors: Counter,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct SocketUsage {
+    sockets_used: u64,
+    tcp_in_use: u64,
+    tcp_orphan: u64,
+    tcp_time_wait: u64,
+    tcp_allocated: u64,
+    tcp_memory_B: u64,
+    udp_in_use: u64,
+    udp_memory_B: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Stats {
+    /// TCP statistics are shared between IPv4 and IPv6
+    tcp: TcpStats,
+    udp: UdpStats,
+    udp6: UdpStats,
+    sockets: SocketUsage,
+}
+
+struct Sample {
+    values: HashMap<String, u64>,
+    rates: Arc<Rates>,
+}
+
+impl Sample {
+    fn value(&self, key: &str) -> u64 {
+        self.values.get(key).cloned().unwrap_or_default()
+    }
+
+    fn counter(&self, key: &str) -> Counter {
+        let value = self.value(key);
+        Counter {
+            value,
+            rate_per_second: self.rates.get(key).and_then(|rates| rates.first()).cloned(),
+        }
+    }
+}
+
+// Parses /proc/net/snmp and /proc/net/netstat, where each section has a header line
+// followed by a value line, e.g: "Udp: InDatagrams NoPorts ..." and "Udp: 123 4 ...".
+// Values are inserted as "Section.Name".
+fn parse_header_value_lines(content: &str, values: &mut HashMap<String, u64>) {
+    let lines: Vec<&str> = content.lines().collect();
+    for pair in lines.chunks(2) {
+        if let [header, value] = pair {
+            let mut headers = header.split_whitespace();
+            let mut fields = value.split_whitespace();
+            let section = match (headers.next(), fields.next()) {
+                (Some(header_section), Some(value_section)) if header_section == value_section => {
+                    header_section.trim_end_matches(':')
+                }
+                _ => continue,
+            };
+            for (name, value) in headers.zip(fields) {
+                if let Ok(value) = value.parse::<u64>() {
+                    values.insert(format!("{section}.{name}"), value);
+                }
+            }
+        }
+    }
+}
+
+// Parses /proc/net/snmp6, formatted as "Udp6InErrors 0", inserted as "Udp6.InErrors"
+fn parse_snmp6(content: &str, values: &mut HashMap<String, u64>) {
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        if let (Some(name), Some(value)) = (fields.next(), fields.next()) {
+            if let (Some(name), Ok(value)) = (name.strip_prefix("Udp6"), value.parse::<u64>()) {
+                values.insert(format!("Udp6.{name}"), value);
+            }
+        }
+    }
+}
+
+// Parses /proc/net/sockstat, formatted as "TCP: inuse 6 orphan 0 tw 0 alloc 8 mem 1",
+// inserted as "TCP.inuse"
+fn parse_sockstat(content: &str, values: &mut HashMap<String, u64>) {
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let section = match fields.next() {
+            Some(section) => section.trim_end_matches(':'),
+            None => continue,
+        };
+        let fields: Vec<&str> = fields.collect();
+        for pair in fields.chunks(2) {
+            if let [name, value] = pair {
+                if let Ok(value) = value.parse::<u64>() {
+                    values.insert(format!("{section}.{name}"), value);
+                }
+            }
+        }
+    }
+}
+
+fn udp_stats(sample: &Sample, section: &str) -> UdpStats {
+    let counter = |name: &str| sample.counter(&format!("{section}.{name}"));
+    UdpStats {
+        in_datagrams: counter("InDatagrams"),
+        out_datagrams: counter("OutDatagrams"),
+        no_ports: counter("NoPorts"),
+        in_errors: counter("InErrors"),
+        receive_buffer_errors: counter("RcvbufErrors"),
+        send_buffer_errors: counter("SndbufErrors"),
+        in_checksum_errors: counter("InCsumErrors"),
+    }
+}
+
+fn values() -> HashMap<String, u64> {
+    let mut values = HashMap::new();
+    parse_header_value_lines(&read_or_warn("/proc/net/snmp"), &mut values);
+    parse_header_value_lines(&read_or_warn("/proc/net/netstat"), &mut values);
+    parse_snmp6(&read_or_warn("/proc/net/snmp6"), &mut values);
+    parse_sockstat(&read_or_warn("/proc/net/sockstat"), &mut values);
+    values
+}
+
+fn counters() -> Counters {
+    values()
+        .into_iter()
+        .map(|(key, value)| (key, vec![value]))
+        .collect()
+}
+
+#[cached(time = 1)]
+pub fn stats() -> Stats {
+    let sample = Sample {
+        values: values(),
+        rates: RATES.rates(),
+    };
+
+    // Socket memory is reported in pages
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(0) as u64;
+
+    Stats {
+        tcp: TcpStats {
+            current_established: sample.value("Tcp.CurrEstab"),
+            active_opens: sample.counter("Tcp.ActiveOpens"),
+            passive_opens: sample.counter("Tcp.PassiveOpens"),
+            attempt_fails: sample.counter("Tcp.AttemptFails"),
+            established_resets: sample.counter("Tcp.EstabResets"),
+            in_segments: sample.counter("Tcp.InSegs"),
+            out_segments: sample.counter("Tcp.OutSegs"),
+            retransmitted_segments: sample.counter("Tcp.RetransSegs"),
+            in_errors: sample.counter("Tcp.InErrs"),
+            out_resets: sample.counter("Tcp.OutRsts"),
+            timeouts: sample.counter("TcpExt.TCPTimeouts"),
+            listen_overflows: sample.counter("TcpExt.ListenOverflows"),
+            listen_drops: sample.counter("TcpExt.ListenDrops"),
+        },
+        udp: udp_stats(&sample, "Udp"),
+        udp6: udp_stats(&sample, "Udp6"),
+        sockets: SocketUsage {
+            sockets_used: sample.value("sockets.used"),
+            tcp_in_use: sample.value("TCP.inuse"),
+            tcp_orphan: sample.value("TCP.orphan"),
+            tcp_time_wait: sample.value("TCP.tw"),
+            tcp_allocated: sample.value("TCP.alloc"),
+            tcp_memory_B: sample.value("TCP.mem") * page_size,
+            udp_in_use: sample.value("UDP.inuse"),
+            udp_memory_B: sample.value("UDP.mem") * page_size,
+        },
+    }
+}
@@ -0,0 +1,303 @@
+use paperclip::actix::Apiv2Schema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::time::Duration;
+use tracing::*;
+
+use crate::features::system::ProcessSummary;
+
+// Netlink sock_diag constants from linux/sock_diag.h and linux/unix_diag.h
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+const UDIAG_SHOW_PEER: u32 = 0x04;
+const UNIX_DIAG_PEER: u16 = 2;
+
+// From linux/net.h, flag used by sockets that accept connections
+const SO_ACCEPTCON: u32 = 0x0001_0000;
+
+// The kernel answers the dump right away, so a missing answer should not block the request
+const NETLINK_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Serialize, Apiv2Schema)]
+pub struct Unix {
+    pub inode: u64,
+    /// Path of the socket file, abstract sockets start with '@'
+    pub path: Option<String>,
+    #[serde(rename = "type")]
+    pub socket_type: String,
+    pub state: String,
+    /// Inode of the socket connected to this one
+    pub peer_inode: Option<u64>,
+    pub pids: Vec<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub processes: Option<Vec<ProcessSummary>>,
+}
+
+#[repr(C)]
+struct UnixDiagRequest {
+    header: libc::nlmsghdr,
+    family: u8,
+    protocol: u8,
+    pad: u16,
+    states: u32,
+    inode: u32,
+    show: u32,
+    cookie: [u32; 2],
+}
+
+fn socket_type(value: &str) -> String {
+    match value {
+        "0001" => "STREAM".into(),
+        "0002" => "DGRAM".into(),
+        "0005" => "SEQPACKET".into(),
+        _ => format!("UNKNOWN({value})"),
+    }
+}
+
+fn socket_state(flags: u32, value: &str) -> String {
+    if flags & SO_ACCEPTCON != 0 {
+        return "LISTENING".into();
+    }
+
+    match value {
+        "01" => "UNCONNECTED".into(),
+        "02" => "CONNECTING".into(),
+        "03" => "CONNECTED".into(),
+        "04" => "DISCONNECTING".into(),
+        _ => format!("UNKNOWN({value})"),
+    }
+}
+
+// Maps socket inodes to the pids that hold a file descriptor to them
+pub fn inode_pids() -> HashMap<u64, Vec<u32>> {
+    let mut inode_pids: HashMap<u64, Vec<u32>> = HashMap::new();
+
+    let proc_dir = match std::fs::read_dir("/proc") {
+        Ok(dir) => dir,
+        Err(error) => {
+            warn!("Failed to read /proc: {error}");
+            return inode_pids;
+        }
+    };
+
+    for entry in proc_dir.flatten() {
+        let pid = match entry.file_name().to_string_lossy().parse::<u32>() {
+            Ok(pid) => pid,
+            Err(_) => continue,
+        };
+
+        // Processes can finish or be inaccessible, skip them silently
+        let fd_dir = match std::fs::read_dir(entry.path().join("fd")) {
+            Ok(dir) => dir,
+            Err(_) => continue,
+        };
+
+        for fd in fd_dir.flatten() {
+            let target = match std::fs::read_link(fd.path()) {
+                Ok(target) => target.to_string_lossy().to_string(),
+                Err(_) => continue,
+            };
+
+            let inode = target
+                .strip_prefix("socket:[")
+                .and_then(|inode| inode.strip_suffix(']'))
+                .and_then(|inode| inode.parse::<u64>().ok());
+
+            if let Some(inode) = inode {
+                let pids = inode_pids.entry(inode).or_default();
+                if !pids.contains(&pid) {
+                    pids.push(pid);
+                }
+            }
+        }
+    }
+
+    inode_pids
+}
+
+fn netlink_align(length: usize) -> usize {
+    (length + 3) & !3
+}
+
+// Asks the kernel for the peer of every unix socket via netlink sock_diag,
+// since /proc/net/unix does not provide it
+fn peers() -> Result<HashMap<u64, u64>, String> {
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            libc::NETLINK_SOCK_DIAG,
+        )
+    };
+    if fd < 0 {
+        return Err(format!(
+            "Failed to open netlink socket: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+
+    let result = (|| {
+        let timeout = libc::timeval {
+            tv_sec: NETLINK_TIMEOUT.as_secs() as libc::time_t,
+            tv_usec: NETLINK_TIMEOUT.subsec_micros() as libc::suseconds_t,
+        };
+        let configured = unsafe {
+            libc::setsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeout as *const libc::timeval as *const libc::c_void,
+                std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if configured < 0 {
+            return Err(format!(
+                "Failed to set netlink socket timeout: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        let request = UnixDiagRequest {
+            header: libc::nlmsghdr {
+                nlmsg_len: std::mem::size_of::<UnixDiagRequest>() as u32,
+                nlmsg_type: SOCK_DIAG_BY_FAMILY,
+                nlmsg_flags: (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16,
+                nlmsg_seq: 0,
+                nlmsg_pid: 0,
+            },
+            family: libc::AF_UNIX as u8,
+            protocol: 0,
+            pad: 0,
+            states: u32::MAX,
+            inode: 0,
+            show: UDIAG_SHOW_PEER,
+            cookie: [u32::MAX; 2],
+        };
+        let sent = unsafe {
+            libc::send(
+                fd,
+                &request as *const UnixDiagRequest as *const libc::c_void,
+                std::mem::size_of::<UnixDiagRequest>(),
+                0,
+            )
+        };
+        if sent < 0 {
+            return Err(format!(
+                "Failed to send netlink request: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        let mut peers = HashMap::new();
+        let mut buffer = vec![0u8; 32768];
+        loop {
+            let received = unsafe {
+                libc::recv(
+                    fd,
+                    buffer.as_mut_ptr() as *mut libc::c_void,
+                    buffer.len(),
+                    0,
+                )
+            };
+            if received < 0 {
+                return Err(format!(
+                    "Failed to receive netlink response: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            if received == 0 {
+                return Err("Netlink response ended before NLMSG_DONE.".into());
+            }
+
+            let data = &buffer[..received as usize];
+            let u32_at = |position: usize| {
+                u32::from_ne_bytes(data[position..position + 4].try_into().unwrap())
+            };
+            let u16_at = |position: usize| {
+                u16::from_ne_bytes(data[position..position + 2].try_into().unwrap())
+            };
+
+            // Each message has a 16 bytes nlmsghdr, followed by a 16 bytes unix_diag_msg
+            // (family, type, state, pad, inode and cookie) and its attributes.
+            // A malformed message would make the next messages unreadable, so it's an error
+            let mut offset = 0;
+            while offset < data.len() {
+                if offset + 16 > data.len() {
+                    return Err("Truncated netlink message header.".into());
+                }
+                let length = u32_at(offset) as usize;
+                if length < 16 || offset + length > data.len() {
+                    return Err(format!("Malformed netlink message length: {length}."));
+                }
+
+                match u16_at(offset + 4) as i32 {
+                    libc::NLMSG_DONE => return Ok(peers),
+                    libc::NLMSG_ERROR => return Err("Netlink request failed.".into()),
+                    _ => {}
+                }
+                if length < 32 {
+                    return Err(format!("Malformed unix_diag message length: {length}."));
+                }
+
+                let inode = u32_at(offset + 20) as u64;
+                let mut attribute = offset + 32;
+                while attribute + 4 <= offset + length {
+                    let attribute_length = u16_at(attribute) as usize;
+                    if attribute_length < 4 {
+                        break;
+                    }
+                    if u16_at(attribute + 2) == UNIX_DIAG_PEER && attribute_length >= 8 {
+                        peers.insert(inode, u32_at(attribute + 4) as u64);
+                    }
+                    attribute += netlink_align(attribute_length);
+                }
+
+                offset += netlink_align(length);
+            }
+        }
+    })();
+
+    unsafe { libc::close(fd) };
+    result
+}
+
+// Parses /proc/net/unix, lines are formatted as: "Num RefCount Protocol Flags Type St Inode [Path]"
+pub fn sockets() -> Vec<Unix> {
+    let content = match std::fs::read_to_string("/proc/net/unix") {
+        Ok(content) => content,
+        Err(error) => {
+            warn!("Failed to read /proc/net/unix: {error}");
+            return vec![];
+        }
+    };
+
+    let peers = peers().unwrap_or_else(|error| {
+        warn!("Failed to get unix socket peers: {error}");
+        HashMap::new()
+    });
+    let inode_pids = inode_pids();
+
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 7 {
+                return None;
+            }
+
+            let flags = u32::from_str_radix(fields[3], 16).unwrap_or_default();
+            let inode = fields[6].parse::<u64>().ok()?;
+
+            Some(Unix {
+                inode,
+                path: Some(fields[7..].join(" ")).filter(|path| !path.is_empty()),
+                socket_type: socket_type(fields[4]),
+                state: socket_state(flags, fields[5]),
+                peer_inode: peers.get(&inode).cloned(),
+                pids: inode_pids.get(&inode).cloned().unwrap_or_default(),
+                processes: None,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,133 @@
+use futures::channel::mpsc::{channel, Receiver, Sender};
+use futures::SinkExt;
+use paperclip::actix::Apiv2Schema;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tracing::*;
+
+use crate::features::sysfs::{name, read_i64, read_string};
+
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+const WEBSOCKET_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, Serialize, PartialEq, Apiv2Schema)]
+pub struct PowerSupply {
+    /// Name of the supply, like "BAT0" or "AC"
+    name: String,
+    /// Battery, Mains, USB, UPS or Wireless
+    #[serde(rename = "type")]
+    supply_type: String,
+    /// True if an external power source is connected
+    online: Option<bool>,
+    /// Charging, Discharging, Not charging or Full
+    status: Option<String>,
+    capacity_percent: Option<u64>,
+    /// Critical, Low, Normal, High or Full
+    capacity_level: Option<String>,
+    voltage_V: Option<f64>,
+    /// Negative values usually mean discharging, but it depends on the driver
+    current_A: Option<f64>,
+    power_W: Option<f64>,
+    /// Batteries report energy or charge, depending on the driver
+    energy_now_Wh: Option<f64>,
+    energy_full_Wh: Option<f64>,
+    charge_now_Ah: Option<f64>,
+    charge_full_Ah: Option<f64>,
+    /// Good, Overheat, Dead, Over voltage, Cold and etc
+    health: Option<String>,
+    technology: Option<String>,
+    temperature: Option<f64>,
+    manufacturer: Option<String>,
+    model_name: Option<String>,
+}
+
+struct PowerSupplyService {
+    senders: Vec<Sender<String>>,
+    _main_loop_thread: std::thread::JoinHandle<()>,
+}
+
+lazy_static! {
+    static ref POWER_SUPPLY_SERVICE: Arc<Mutex<PowerSupplyService>> =
+        Arc::new(Mutex::new(PowerSupplyService {
+            senders: Default::default(),
+            _main_loop_thread: thread::spawn(move || run_main_loop()),
+        }));
+}
+
+// Values are reported in micro units (µV, µA, µW, µWh and µAh), check the kernel documentation:
+// https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-class-power
+fn read_micro(path: &Path) -> Option<f64> {
+    read_i64(path).map(|value| value as f64 / 1_000_000.0)
+}
+
+fn power_supply_from(path: &Path) -> PowerSupply {
+    let attribute = |name: &str| path.join(name);
+    PowerSupply {
+        name: name(path),
+        supply_type: read_string(&attribute("type")).unwrap_or_default(),
+        online: read_i64(&attribute("online")).map(|online| online != 0),
+        status: read_string(&attribute("status")),
+        capacity_percent: read_i64(&attribute("capacity")).map(|capacity| capacity.max(0) as u64),
+        capacity_level: read_string(&attribute("capacity_level")),
+        voltage_V: read_micro(&attribute("voltage_now")),
+        current_A: read_micro(&attribute("current_now")),
+        power_W: read_micro(&attribute("power_now")),
+        energy_now_Wh: read_micro(&attribute("energy_now")),
+        energy_full_Wh: read_micro(&attribute("energy_full")),
+        charge_now_Ah: read_micro(&attribute("charge_now")),
+        charge_full_Ah: read_micro(&attribute("charge_full")),
+        health: read_string(&attribute("health")),
+        technology: read_string(&attribute("technology")),
+        // Temperature is in tenths of degree Celsius
+        temperature: read_i64(&attribute("temp")).map(|temperature| temperature as f64 / 10.0),
+        manufacturer: read_string(&attribute("manufacturer")),
+        model_name: read_string(&attribute("model_name")),
+    }
+}
+
+pub fn power_supply() -> Vec<PowerSupply> {
+    let entries = match fs::read_dir(POWER_SUPPLY_PATH) {
+        Ok(entries) => entries,
+        Err(error) => {
+            warn!("Failed to read {POWER_SUPPLY_PATH}: {error}");
+            return vec![];
+        }
+    };
+
+    let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    paths.iter().map(|path| power_supply_from(path)).collect()
+}
+
+pub fn ask_for_client() -> Receiver<String> {
+    let (mut sender, receiver) = channel(1024);
+
+    let mut power_supply_service = POWER_SUPPLY_SERVICE.as_ref().lock().unwrap();
+    let _ =
+        futures::executor::block_on(sender.send(serde_json::json!(&power_supply()).to_string()));
+    power_supply_service.senders.push(sender);
+
+    return receiver;
+}
+
+// Sends the power supplies to the websocket clients periodically, if there is any
+fn run_main_loop() {
+    loop {
+        thread::sleep(WEBSOCKET_INTERVAL);
+
+        let mut power_supply_service = POWER_SUPPLY_SERVICE.as_ref().lock().unwrap();
+        if power_supply_service.senders.is_empty() {
+            continue;
+        }
+
+        let message = serde_json::json!(&power_supply()).to_string();
+        power_supply_service.senders.retain(|sender| {
+            let mut sender = sender.clone();
+            futures::executor::block_on(sender.send(message.clone())).is_ok()
+        });
+    }
+}
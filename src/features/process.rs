@@ -0,0 +1,592 @@
+use paperclip::actix::Apiv2Schema;
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+use tracing::*;
+
+use crate::features::sysfs::read_i64;
+use crate::features::system::{self, Process};
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Thread {
+    tid: u32,
+    name: String,
+    state: String,
+    cpu_usage: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct FileDescriptor {
+    fd: u32,
+    /// File, socket, pipe or device that the file descriptor points to
+    target: String,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Limit {
+    name: String,
+    /// Value or "unlimited"
+    soft: String,
+    /// Value or "unlimited"
+    hard: String,
+    units: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Apiv2Schema)]
+pub struct MemoryRollup {
+    /// Resident set size: memory in RAM, including shared memory
+    rss_kB: u64,
+    /// Proportional set size: private memory plus its share of shared memory
+    pss_kB: u64,
+    /// Unique set size: memory that is private to the process
+    uss_kB: u64,
+    swap_kB: u64,
+    swap_pss_kB: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Scheduling {
+    nice: i64,
+    /// Kernel priority, for real-time policies this is -1 minus the real-time priority
+    priority: i64,
+    policy: String,
+    realtime_priority: u64,
+    /// CPUs in which the process is allowed to run
+    cpu_affinity: Vec<usize>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Apiv2Schema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SchedulingPolicy {
+    SchedOther,
+    SchedFifo,
+    SchedRr,
+    SchedBatch,
+    SchedIdle,
+}
+
+impl SchedulingPolicy {
+    fn number(&self) -> libc::c_int {
+        match self {
+            SchedulingPolicy::SchedOther => libc::SCHED_OTHER,
+            SchedulingPolicy::SchedFifo => libc::SCHED_FIFO,
+            SchedulingPolicy::SchedRr => libc::SCHED_RR,
+            SchedulingPolicy::SchedBatch => libc::SCHED_BATCH,
+            SchedulingPolicy::SchedIdle => libc::SCHED_IDLE,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct SchedulingRequest {
+    /// Nice value, between -20 (highest priority) and 19 (lowest priority)
+    nice: Option<i32>,
+    /// Scheduling policy, SCHED_FIFO and SCHED_RR require a realtime_priority
+    policy: Option<SchedulingPolicy>,
+    /// Real-time priority between 1 and 99, only valid for SCHED_FIFO and SCHED_RR
+    realtime_priority: Option<i32>,
+    /// CPUs in which the process is allowed to run
+    cpu_affinity: Option<Vec<usize>>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct ProcessDetails {
+    process: Process,
+    group: String,
+    oom_score: Option<i64>,
+    oom_score_adj: Option<i64>,
+    memory: Option<MemoryRollup>,
+    limits: Vec<Limit>,
+    threads: Vec<Thread>,
+    file_descriptors: Vec<FileDescriptor>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Apiv2Schema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Signal {
+    Term,
+    Kill,
+    Hup,
+    Int,
+    Usr1,
+    Stop,
+    Cont,
+}
+
+impl Signal {
+    fn number(&self) -> libc::c_int {
+        match self {
+            Signal::Term => libc::SIGTERM,
+            Signal::Kill => libc::SIGKILL,
+            Signal::Hup => libc::SIGHUP,
+            Signal::Int => libc::SIGINT,
+            Signal::Usr1 => libc::SIGUSR1,
+            Signal::Stop => libc::SIGSTOP,
+            Signal::Cont => libc::SIGCONT,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct SignalRequest {
+    signal: Signal,
+    /// Process that receives the signal, exclusive with name
+    pid: Option<u32>,
+    /// Exact name of the processes that receive the signal, exclusive with pid
+    name: Option<String>,
+    /// Allows signaling init (pid 1) and linux2rest itself (default: false)
+    force: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct SignalResult {
+    pid: u32,
+    name: Option<String>,
+    success: bool,
+    error: Option<String>,
+}
+
+// Fields of /proc/<pid>/stat, the process name is in parentheses and may contain spaces,
+// so the remaining fields are split after the last parenthesis.
+// Check proc(5) for the fields description.
+struct Stat {
+    name: String,
+    state: char,
+    priority: i64,
+    nice: i64,
+    realtime_priority: u64,
+    policy: u64,
+    user_time: u64,
+    system_time: u64,
+}
+
+impl Stat {
+    fn read(path: &str) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        let name_start = content.find('(')?;
+        let name_end = content.rfind(')')?;
+        let fields: Vec<&str> = content[name_end + 1..].split_whitespace().collect();
+        // Field numbers in proc(5) start at 1 and the first fields are pid and name
+        let field = |number: usize| fields.get(number - 3).cloned().unwrap_or_default();
+
+        Some(Stat {
+            name: content[name_start + 1..name_end].to_string(),
+            state: field(3).chars().next().unwrap_or('?'),
+            priority: field(18).parse().unwrap_or_default(),
+            nice: field(19).parse().unwrap_or_default(),
+            realtime_priority: field(40).parse().unwrap_or_default(),
+            policy: field(41).parse().unwrap_or_default(),
+            user_time: field(14).parse().unwrap_or_default(),
+            system_time: field(15).parse().unwrap_or_default(),
+        })
+    }
+}
+
+fn state_name(state: char) -> String {
+    match state {
+        'R' => "Running".into(),
+        'S' => "Sleeping".into(),
+        'D' => "DiskSleep".into(),
+        'Z' => "Zombie".into(),
+        'T' => "Stopped".into(),
+        't' => "TracingStop".into(),
+        'X' | 'x' => "Dead".into(),
+        'K' => "Wakekill".into(),
+        'W' => "Waking".into(),
+        'P' => "Parked".into(),
+        'I' => "Idle".into(),
+        _ => format!("Unknown({state})"),
+    }
+}
+
+fn policy_name(policy: u64) -> String {
+    match policy {
+        0 => "SCHED_OTHER".into(),
+        1 => "SCHED_FIFO".into(),
+        2 => "SCHED_RR".into(),
+        3 => "SCHED_BATCH".into(),
+        5 => "SCHED_IDLE".into(),
+        6 => "SCHED_DEADLINE".into(),
+        _ => format!("Unknown({policy})"),
+    }
+}
+
+fn cpu_affinity(pid: u32) -> Vec<usize> {
+    let mut cpu_set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    let result = unsafe {
+        libc::sched_getaffinity(
+            pid as libc::pid_t,
+            std::mem::size_of::<libc::cpu_set_t>(),
+            &mut cpu_set,
+        )
+    };
+    if result != 0 {
+        return vec![];
+    }
+    (0..libc::CPU_SETSIZE as usize)
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &cpu_set) })
+        .collect()
+}
+
+impl Stat {
+    fn scheduling(&self, pid: u32) -> Scheduling {
+        Scheduling {
+            nice: self.nice,
+            priority: self.priority,
+            policy: policy_name(self.policy),
+            realtime_priority: self.realtime_priority,
+            cpu_affinity: cpu_affinity(pid),
+        }
+    }
+
+    // Times in /proc/<pid>/stat are in clock ticks
+    fn cpu_time(&self) -> (f64, f64) {
+        let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as f64;
+        (
+            self.user_time as f64 / ticks_per_second,
+            self.system_time as f64 / ticks_per_second,
+        )
+    }
+}
+
+pub fn scheduling(pid: u32) -> Option<Scheduling> {
+    Stat::read(&format!("/proc/{pid}/stat")).map(|stat| stat.scheduling(pid))
+}
+
+// Provides the cumulative user and system CPU time of a process in seconds, and its scheduling,
+// reading /proc/<pid>/stat a single time
+pub fn cpu_time_and_scheduling(pid: u32) -> Option<((f64, f64), Scheduling)> {
+    let stat = Stat::read(&format!("/proc/{pid}/stat"))?;
+    Some((stat.cpu_time(), stat.scheduling(pid)))
+}
+
+fn threads(pid: u32) -> Vec<Thread> {
+    let cpu_usage = system::thread_cpu_usage(pid);
+    let task_dir = match fs::read_dir(format!("/proc/{pid}/task")) {
+        Ok(dir) => dir,
+        Err(_) => return vec![],
+    };
+
+    let mut threads: Vec<Thread> = task_dir
+        .flatten()
+        .filter_map(|entry| {
+            let tid = entry.file_name().to_string_lossy().parse::<u32>().ok()?;
+            let stat = Stat::read(&format!("/proc/{pid}/task/{tid}/stat"))?;
+            Some(Thread {
+                tid,
+                name: stat.name,
+                state: state_name(stat.state),
+                cpu_usage: cpu_usage.get(&tid).cloned().unwrap_or_default(),
+            })
+        })
+        .collect();
+    threads.sort_by_key(|thread| thread.tid);
+    threads
+}
+
+fn file_descriptors(pid: u32) -> Vec<FileDescriptor> {
+    let fd_dir = match fs::read_dir(format!("/proc/{pid}/fd")) {
+        Ok(dir) => dir,
+        Err(_) => return vec![],
+    };
+
+    let mut file_descriptors: Vec<FileDescriptor> = fd_dir
+        .flatten()
+        .filter_map(|entry| {
+            Some(FileDescriptor {
+                fd: entry.file_name().to_string_lossy().parse::<u32>().ok()?,
+                target: fs::read_link(entry.path())
+                    .ok()?
+                    .to_string_lossy()
+                    .to_string(),
+            })
+        })
+        .collect();
+    file_descriptors.sort_by_key(|file_descriptor| file_descriptor.fd);
+    file_descriptors
+}
+
+// /proc/<pid>/limits is a table aligned by the header columns:
+// "Limit                     Soft Limit           Hard Limit           Units"
+fn limits(pid: u32) -> Vec<Limit> {
+    let content = fs::read_to_string(format!("/proc/{pid}/limits")).unwrap_or_default();
+    let mut lines = content.lines();
+    let header = match lines.next() {
+        Some(header) => header,
+        None => return vec![],
+    };
+
+    let (soft_start, hard_start, units_start) = match (
+        header.find("Soft Limit"),
+        header.find("Hard Limit"),
+        header.find("Units"),
+    ) {
+        (Some(soft), Some(hard), Some(units)) => (soft, hard, units),
+        _ => return vec![],
+    };
+
+    let column = |line: &str, start: usize, end: usize| -> String {
+        line.get(start..end.min(line.len()))
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+
+    lines
+        .filter(|line| line.len() > soft_start)
+        .map(|line| Limit {
+            name: column(line, 0, soft_start),
+            soft: column(line, soft_start, hard_start),
+            hard: column(line, hard_start, units_start),
+            units: Some(column(line, units_start, line.len())).filter(|units| !units.is_empty()),
+        })
+        .collect()
+}
+
+fn memory(pid: u32) -> Option<MemoryRollup> {
+    let content = fs::read_to_string(format!("/proc/{pid}/smaps_rollup")).ok()?;
+    let mut memory = MemoryRollup::default();
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let (name, value) = match (fields.next(), fields.next()) {
+            (Some(name), Some(value)) => (name, value.parse::<u64>().unwrap_or_default()),
+            _ => continue,
+        };
+        match name {
+            "Rss:" => memory.rss_kB = value,
+            "Pss:" => memory.pss_kB = value,
+            "Private_Clean:" | "Private_Dirty:" => memory.uss_kB += value,
+            "Swap:" => memory.swap_kB = value,
+            "SwapPss:" => memory.swap_pss_kB = value,
+            _ => {}
+        }
+    }
+    Some(memory)
+}
+
+// Gets the real group id from /proc/<pid>/status and its name from /etc/group
+fn group(pid: u32) -> String {
+    let status = fs::read_to_string(format!("/proc/{pid}/status")).unwrap_or_default();
+    let gid = match status
+        .lines()
+        .find_map(|line| line.strip_prefix("Gid:"))
+        .and_then(|gids| gids.split_whitespace().next())
+    {
+        Some(gid) => gid.to_string(),
+        None => return Default::default(),
+    };
+
+    fs::read_to_string("/etc/group")
+        .unwrap_or_default()
+        .lines()
+        .find_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            match fields.as_slice() {
+                [name, _password, id, ..] if *id == gid => Some(name.to_string()),
+                _ => None,
+            }
+        })
+        .unwrap_or(gid)
+}
+
+pub fn details(pid: u32, environment: bool) -> Option<ProcessDetails> {
+    let process = system::process_by_pid(pid, environment)?;
+
+    Some(ProcessDetails {
+        process,
+        group: group(pid),
+        oom_score: read_i64(Path::new(&format!("/proc/{pid}/oom_score"))),
+        oom_score_adj: read_i64(Path::new(&format!("/proc/{pid}/oom_score_adj"))),
+        memory: memory(pid),
+        limits: limits(pid),
+        threads: threads(pid),
+        file_descriptors: file_descriptors(pid),
+    })
+}
+
+// The kernel truncates names to 15 characters (TASK_COMM_LEN - 1)
+const MAX_NAME_LENGTH: usize = 15;
+
+// Pids from the process list may be a few seconds old, so the process may have exited
+// and its pid reused by another one
+fn check_name(pid: u32, name: &str) -> Result<(), String> {
+    let stat = Stat::read(&format!("/proc/{pid}/stat"))
+        .ok_or_else(|| format!("Process {pid} no longer exists."))?;
+    let matches =
+        stat.name == name || (stat.name.len() == MAX_NAME_LENGTH && name.starts_with(&stat.name));
+    if !matches {
+        return Err(format!(
+            "Process {pid} is now named '{}' instead of '{name}'.",
+            stat.name
+        ));
+    }
+    Ok(())
+}
+
+fn send_signal(pid: u32, signal: Signal, force: bool) -> Result<(), String> {
+    if !force && pid == 1 {
+        return Err("Refusing to signal init (pid 1) without force.".into());
+    }
+    if !force && pid == std::process::id() {
+        return Err("Refusing to signal linux2rest itself without force.".into());
+    }
+
+    let pid: libc::pid_t = pid.try_into().map_err(|_| format!("Invalid pid: {pid}"))?;
+    // Zero and negative values would signal process groups
+    if pid <= 0 {
+        return Err(format!("Invalid pid: {pid}"));
+    }
+
+    if unsafe { libc::kill(pid, signal.number()) } != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(())
+}
+
+pub fn signal(request: &SignalRequest) -> Result<Vec<SignalResult>, String> {
+    let pids = match (request.pid, &request.name) {
+        (Some(pid), None) => vec![pid],
+        (None, Some(name)) => {
+            let pids = system::pids_by_name(name);
+            if pids.is_empty() {
+                return Err(format!("No process named '{name}' was found."));
+            }
+            pids
+        }
+        _ => return Err("Either pid or name must be provided.".into()),
+    };
+
+    let force = request.force.unwrap_or(false);
+    let summaries = system::process_summaries(&pids);
+
+    Ok(pids
+        .into_iter()
+        .map(|pid| {
+            let result = match &request.name {
+                Some(name) => check_name(pid, name),
+                None => Ok(()),
+            }
+            .and_then(|()| send_signal(pid, request.signal, force));
+            match &result {
+                Ok(()) => info!("Sent {:?} to process {pid}", request.signal),
+                Err(error) => warn!(
+                    "Failed to send {:?} to process {pid}: {error}",
+                    request.signal
+                ),
+            }
+            SignalResult {
+                pid,
+                name: summaries.get(&pid).map(|summary| summary.name.clone()),
+                success: result.is_ok(),
+                error: result.err(),
+            }
+        })
+        .collect())
+}
+
+fn task_ids(pid: u32) -> Vec<libc::pid_t> {
+    fs::read_dir(format!("/proc/{pid}/task"))
+        .map(|dir| {
+            dir.flatten()
+                .filter_map(|entry| entry.file_name().to_string_lossy().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn last_os_error(action: &str, tid: libc::pid_t) -> String {
+    format!(
+        "Failed to set {action} of thread {tid}: {}",
+        std::io::Error::last_os_error()
+    )
+}
+
+// Nice value, scheduling policy and affinity are per thread, so the changes are applied to all of them
+pub fn set_scheduling(pid: u32, request: &SchedulingRequest) -> Result<Scheduling, String> {
+    let current = scheduling(pid).ok_or_else(|| format!("Process {pid} not found."))?;
+
+    if let Some(nice) = request.nice {
+        if !(-20..=19).contains(&nice) {
+            return Err(format!(
+                "Invalid nice value {nice}, it must be between -20 and 19."
+            ));
+        }
+    }
+
+    let policy = match request.policy {
+        Some(policy) => Some(policy),
+        None if request.realtime_priority.is_some() => match current.policy.as_str() {
+            "SCHED_FIFO" => Some(SchedulingPolicy::SchedFifo),
+            "SCHED_RR" => Some(SchedulingPolicy::SchedRr),
+            _ => {
+                return Err(
+                    "A realtime_priority requires the SCHED_FIFO or SCHED_RR policy.".into(),
+                )
+            }
+        },
+        None => None,
+    };
+    let realtime_priority = match policy {
+        Some(SchedulingPolicy::SchedFifo) | Some(SchedulingPolicy::SchedRr) => {
+            match request.realtime_priority {
+                Some(priority) if (1..=99).contains(&priority) => priority,
+                _ => {
+                    return Err(
+                        "SCHED_FIFO and SCHED_RR require a realtime_priority between 1 and 99."
+                            .into(),
+                    )
+                }
+            }
+        }
+        _ if request.realtime_priority.is_some() => {
+            return Err("A realtime_priority requires the SCHED_FIFO or SCHED_RR policy.".into())
+        }
+        _ => 0,
+    };
+
+    let cpu_set = match &request.cpu_affinity {
+        Some(cpus) => {
+            if cpus.is_empty() {
+                return Err("At least one CPU must be provided in cpu_affinity.".into());
+            }
+            let mut cpu_set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+            for &cpu in cpus {
+                if cpu >= libc::CPU_SETSIZE as usize {
+                    return Err(format!("Invalid CPU {cpu}."));
+                }
+                unsafe { libc::CPU_SET(cpu, &mut cpu_set) };
+            }
+            Some(cpu_set)
+        }
+        None => None,
+    };
+
+    for tid in task_ids(pid) {
+        if let Some(policy) = policy {
+            let parameters = libc::sched_param {
+                sched_priority: realtime_priority,
+            };
+            if unsafe { libc::sched_setscheduler(tid, policy.number(), &parameters) } != 0 {
+                return Err(last_os_error("scheduling policy", tid));
+            }
+        }
+
+        // Nice values are only used by SCHED_OTHER and SCHED_BATCH, but can be set for any policy
+        if let Some(nice) = request.nice {
+            if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, nice) } != 0 {
+                return Err(last_os_error("nice value", tid));
+            }
+        }
+
+        if let Some(cpu_set) = &cpu_set {
+            let size = std::mem::size_of::<libc::cpu_set_t>();
+            if unsafe { libc::sched_setaffinity(tid, size, cpu_set) } != 0 {
+                return Err(last_os_error("CPU affinity", tid));
+            }
+        }
+    }
+
+    info!("Scheduling of process {pid} changed: {request:?}");
+    scheduling(pid).ok_or_else(|| format!("Process {pid} not found."))
+}
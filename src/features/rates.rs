@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Cumulative counters by name, with one value per column, like one per CPU
+pub type Counters = HashMap<String, Vec<u64>>;
+
+/// Increase per second of each counter column
+pub type Rates = HashMap<String, Vec<f64>>;
+
+// Counters are read by a thread on a fixed interval, so the rates have the same window
+// regardless of when, or by how many clients, they are requested
+pub struct RateSampler {
+    rates: Arc<Mutex<Arc<Rates>>>,
+}
+
+impl RateSampler {
+    pub fn start(interval: Duration, read: fn() -> Counters) -> Self {
+        let rates: Arc<Mutex<Arc<Rates>>> = Default::default();
+        let thread_rates = rates.clone();
+        thread::spawn(move || {
+            let mut previous: Option<(Instant, Counters)> = None;
+            loop {
+                let now = Instant::now();
+                let counters = read();
+                if let Some((instant, previous)) = &previous {
+                    let elapsed = now.duration_since(*instant).as_secs_f64();
+                    *thread_rates.lock().unwrap() =
+                        Arc::new(rates_between(previous, &counters, elapsed));
+                }
+                previous = Some((now, counters));
+                thread::sleep(interval);
+            }
+        });
+        Self { rates }
+    }
+
+    /// Rates between the two latest samples, empty until two samples are taken
+    pub fn rates(&self) -> Arc<Rates> {
+        self.rates.lock().unwrap().clone()
+    }
+}
+
+fn rates_between(previous: &Counters, latest: &Counters, elapsed: f64) -> Rates {
+    if elapsed <= 0.0 {
+        return Default::default();
+    }
+
+    latest
+        .iter()
+        .filter_map(|(key, values)| {
+            let previous = previous.get(key)?;
+            // Columns change when CPUs go online or offline
+            if previous.len() != values.len() {
+                return None;
+            }
+            let rates = values
+                .iter()
+                .zip(previous)
+                .map(|(value, previous)| value.saturating_sub(*previous) as f64 / elapsed)
+                .collect();
+            Some((key.clone(), rates))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counters(values: &[(&str, Vec<u64>)]) -> Counters {
+        values
+            .iter()
+            .map(|(key, values)| (key.to_string(), values.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn rates_per_second() {
+        let previous = counters(&[("a", vec![10, 20]), ("b", vec![5])]);
+        let latest = counters(&[("a", vec![30, 20]), ("b", vec![9])]);
+        let rates = rates_between(&previous, &latest, 2.0);
+        assert_eq!(rates.get("a"), Some(&vec![10.0, 0.0]));
+        assert_eq!(rates.get("b"), Some(&vec![2.0]));
+    }
+
+    #[test]
+    fn new_and_changed_counters_have_no_rates() {
+        let previous = counters(&[("cpus", vec![1, 2])]);
+        let latest = counters(&[("cpus", vec![1, 2, 3]), ("new", vec![1])]);
+        assert!(rates_between(&previous, &latest, 1.0).is_empty());
+    }
+
+    #[test]
+    fn reset_counters_do_not_underflow() {
+        let previous = counters(&[("a", vec![100])]);
+        let latest = counters(&[("a", vec![3])]);
+        let rates = rates_between(&previous, &latest, 1.0);
+        assert_eq!(rates.get("a"), Some(&vec![0.0]));
+    }
+
+    #[test]
+    fn no_elapsed_time() {
+        let previous = counters(&[("a", vec![1])]);
+        assert!(rates_between(&previous, &previous, 0.0).is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+use paperclip::actix::Apiv2Schema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::*;
+
+use crate::cli;
+
+const SYSCTL_PATH: &str = "/proc/sys";
+const PERSISTENCE_PATH: &str = "/etc/sysctl.d/90-linux2rest.conf";
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct SysctlEntry {
+    /// Key in dot notation, like "net.core.rmem_max"
+    key: String,
+    /// Not available for write-only or protected keys
+    value: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct SysctlRequest {
+    key: String,
+    value: String,
+    /// Also write the value to /etc/sysctl.d, so it survives a reboot (default: false)
+    persistent: Option<bool>,
+}
+
+// Keys use dots as separators, unless they contain slashes,
+// which allows names with dots, like "net/ipv4/conf/eth0.100/forwarding"
+fn key_path(key: &str) -> Result<PathBuf, String> {
+    let key = key.trim().trim_matches(|c| c == '.' || c == '/');
+    let components: Vec<&str> = if key.contains('/') {
+        key.split('/').collect()
+    } else {
+        key.split('.').collect()
+    };
+
+    let valid = components
+        .iter()
+        .all(|component| !component.is_empty() && *component != "." && *component != "..");
+    if !valid {
+        return Err(format!("Invalid sysctl key '{key}'."));
+    }
+
+    Ok(components
+        .iter()
+        .fold(PathBuf::from(SYSCTL_PATH), |path, component| {
+            path.join(component)
+        }))
+}
+
+// Names with dots are kept with slashes, so the key can be used again
+fn path_key(path: &Path) -> String {
+    let components: Vec<String> = path
+        .strip_prefix(SYSCTL_PATH)
+        .unwrap_or(path)
+        .iter()
+        .map(|component| component.to_string_lossy().to_string())
+        .collect();
+    if components.iter().any(|component| component.contains('.')) {
+        components.join("/")
+    } else {
+        components.join(".")
+    }
+}
+
+fn entry(path: &Path) -> SysctlEntry {
+    SysctlEntry {
+        key: path_key(path),
+        // Multiple values are separated by tabs, like "4096\t131072\t6291456"
+        value: fs::read_to_string(path)
+            .ok()
+            .map(|value| value.split_whitespace().collect::<Vec<&str>>().join(" ")),
+    }
+}
+
+fn walk(path: &Path, entries: &mut Vec<SysctlEntry>) {
+    if !path.is_dir() {
+        entries.push(entry(path));
+        return;
+    }
+
+    let mut children: Vec<PathBuf> = match fs::read_dir(path) {
+        Ok(children) => children.flatten().map(|child| child.path()).collect(),
+        Err(error) => {
+            warn!("Failed to read {path:?}: {error}");
+            return;
+        }
+    };
+    children.sort();
+    for child in children {
+        walk(&child, entries);
+    }
+}
+
+// Provides a single key, or all keys under a subtree, like "net.core"
+pub fn sysctl(key: Option<String>) -> Result<Vec<SysctlEntry>, String> {
+    let path = match &key {
+        Some(key) if !key.trim().is_empty() => key_path(key)?,
+        _ => PathBuf::from(SYSCTL_PATH),
+    };
+    if !path.exists() {
+        return Err(format!(
+            "Sysctl key '{}' not found.",
+            key.unwrap_or_default()
+        ));
+    }
+
+    let mut entries = vec![];
+    walk(&path, &mut entries);
+    Ok(entries)
+}
+
+// Allowed keys are configured as exact keys or subtrees, "net.core" allows "net.core.rmem_max".
+// Paths are compared by whole components, so "net.ipv4.conf.eth0" does not allow "net/ipv4/conf/eth0.100"
+fn is_allowed(allowlist: &[String], path: &Path) -> bool {
+    allowlist
+        .iter()
+        .filter_map(|allowed| key_path(allowed).ok())
+        .any(|allowed| path.starts_with(allowed))
+}
+
+fn persist(key: &str, value: &str) -> Result<(), String> {
+    let content = fs::read_to_string(PERSISTENCE_PATH).unwrap_or_default();
+    let setting = format!("{key} = {value}");
+
+    let mut found = false;
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| match line.split_once('=') {
+            Some((line_key, _)) if line_key.trim() == key => {
+                found = true;
+                setting.clone()
+            }
+            _ => line.to_string(),
+        })
+        .collect();
+    if !found {
+        lines.push(setting);
+    }
+
+    fs::write(PERSISTENCE_PATH, format!("{}\n", lines.join("\n")))
+        .map_err(|error| format!("Failed to write {PERSISTENCE_PATH}: {error}"))
+}
+
+pub fn set_sysctl(request: &SysctlRequest) -> Result<SysctlEntry, String> {
+    let path = key_path(&request.key)?;
+    let key = path_key(&path);
+    if !is_allowed(&cli::args().sysctl_allowlist, &path) {
+        return Err(format!(
+            "Sysctl key '{key}' is not in the allowlist, check --sysctl-allowlist."
+        ));
+    }
+    if !path.is_file() {
+        return Err(format!("Sysctl key '{key}' not found."));
+    }
+
+    let value = request.value.trim();
+    if value.contains('\n') {
+        return Err("Sysctl values must have a single line.".into());
+    }
+
+    fs::write(&path, value).map_err(|error| format!("Failed to set '{key}': {error}"))?;
+    info!("Sysctl {key} changed to: {value}");
+
+    if request.persistent.unwrap_or(false) {
+        persist(&key, value)?;
+    }
+
+    Ok(entry(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(allowlist: &[&str], key: &str) -> bool {
+        let allowlist: Vec<String> = allowlist.iter().map(|key| key.to_string()).collect();
+        is_allowed(&allowlist, &key_path(key).unwrap())
+    }
+
+    #[test]
+    fn exact_key() {
+        assert!(allowed(&["vm.swappiness"], "vm.swappiness"));
+        assert!(!allowed(&["vm.swappiness"], "vm.swappiness_extra"));
+        assert!(!allowed(&["vm.swappiness"], "vm.overcommit_memory"));
+        assert!(!allowed(&["vm.swappiness"], "vm"));
+    }
+
+    #[test]
+    fn subtree() {
+        assert!(allowed(&["net.core"], "net.core.rmem_max"));
+        assert!(allowed(&["net.core"], "net.core"));
+        assert!(!allowed(&["net.core"], "net.core_extra.rmem_max"));
+        assert!(!allowed(&["net.core"], "net.ipv4.ip_forward"));
+    }
+
+    #[test]
+    fn slash_separated_key() {
+        assert!(allowed(&["net/core"], "net.core.rmem_max"));
+        assert!(allowed(&["net.core"], "net/core/rmem_max"));
+        assert!(allowed(
+            &["net/ipv4/conf/eth0.100"],
+            "net/ipv4/conf/eth0.100/forwarding"
+        ));
+    }
+
+    #[test]
+    fn dotted_interface_name() {
+        assert!(!allowed(
+            &["net.ipv4.conf.eth0"],
+            "net/ipv4/conf/eth0.100/forwarding"
+        ));
+        assert!(!allowed(
+            &["net.ipv4.conf.eth0"],
+            "net/ipv4/conf/eth0.foo/rp_filter"
+        ));
+        assert!(allowed(
+            &["net.ipv4.conf.eth0"],
+            "net/ipv4/conf/eth0/forwarding"
+        ));
+    }
+
+    #[test]
+    fn invalid_and_empty_entries_allow_nothing() {
+        assert!(!allowed(&["", " ", "net..core"], "net.core.rmem_max"));
+        assert!(!allowed(&[], "net.core.rmem_max"));
+    }
+}
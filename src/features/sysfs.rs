@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::*;
+
+// Helpers for sysfs and procfs attributes, files that have a single value followed by a new line
+
+// Files that are expected to exist, like the ones in /proc, are logged when they can't be read
+pub fn read_or_warn(path: &str) -> String {
+    fs::read_to_string(path).unwrap_or_else(|error| {
+        warn!("Failed to read {path}: {error}");
+        Default::default()
+    })
+}
+
+// Name of a device directory, like "thermal_zone0" or "BAT0"
+pub fn name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// Missing, unreadable and empty attributes are not available
+pub fn read_string(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty())
+}
+
+pub fn read_number<T: FromStr>(path: &Path) -> Option<T> {
+    read_string(path)?.parse().ok()
+}
+
+pub fn read_i64(path: &Path) -> Option<i64> {
+    read_number(path)
+}
+
+pub fn read_u64(path: &Path) -> Option<u64> {
+    read_number(path)
+}
+
+// Attributes with values separated by spaces, like "performance powersave"
+pub fn read_list<T: FromStr>(path: &Path) -> Vec<T> {
+    read_string(path)
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter_map(|value| value.parse().ok())
+        .collect()
+}
+
+// Parses CPU lists like "0-3,6", used by attributes like /proc/irq/<irq>/smp_affinity_list
+pub fn parse_cpu_list(list: &str) -> Vec<usize> {
+    list.split(',')
+        .flat_map(|range| {
+            let mut bounds = range.trim().splitn(2, '-');
+            let start = bounds.next().and_then(|start| start.parse::<usize>().ok());
+            let end = bounds.next().map_or(start, |end| end.parse::<usize>().ok());
+            match (start, end) {
+                (Some(start), Some(end)) => start..end + 1,
+                _ => 0..0,
+            }
+        })
+        .collect()
+}
+
+// Provides the directories named prefix followed by a number, sorted by the number,
+// like "thermal_zone10" after "thermal_zone9"
+pub fn indexed_directories(path: &str, prefix: &str) -> Vec<(u32, PathBuf)> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(error) => {
+            warn!("Failed to read {path}: {error}");
+            return vec![];
+        }
+    };
+
+    let mut directories: Vec<(u32, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let index = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix(prefix)?
+                .parse::<u32>()
+                .ok()?;
+            Some((index, entry.path()))
+        })
+        .collect();
+    directories.sort();
+    directories
+}
@@ -1,17 +1,23 @@
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
 use sysinfo::CpuExt;
 use sysinfo::CpuRefreshKind;
-use sysinfo::PidExt;
+use sysinfo::{Pid, PidExt};
 
 use cached::proc_macro::cached;
 use paperclip::actix::Apiv2Schema;
 use pnet;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sysinfo::{
     ComponentExt, DiskExt, NetworkExt, NetworksExt, ProcessExt, System as sysSystem, SystemExt,
+    UserExt,
 };
 use tracing::*;
 
+use crate::cli;
+use crate::features::{cgroup, process};
+
 lazy_static! {
     static ref SYSTEM: Arc<Mutex<sysSystem>> = Arc::new(Mutex::new(sysSystem::new()));
 }
@@ -95,22 +101,318 @@ pub struct DiskUsage {
     read_bytes: u64,
 }
 
+// Fields that can be selected with the fields parameter of the process list
+const PROCESS_FIELDS: &[&str] = &[
+    "name",
+    "pid",
+    "user",
+    "status",
+    "command",
+    "executable_path",
+    "environment",
+    "working_directory",
+    "root_directory",
+    "used_memory_kB",
+    "virtual_memory_kB",
+    "parent_process",
+    "start_time",
+    "running_time",
+    "cpu_usage",
+    "user_time_s",
+    "system_time_s",
+    "disk_usage",
+    "scheduling",
+    "cgroup",
+    "container_id",
+];
+
+// Fields that are not provided by sysinfo and require reading more files from /proc
+const PROCESS_DETAIL_FIELDS: &[&str] = &[
+    "user_time_s",
+    "system_time_s",
+    "scheduling",
+    "cgroup",
+    "container_id",
+];
+
+// Fields are only missing when they are not available, or not selected in the process list
 #[derive(Clone, Debug, Serialize, Apiv2Schema)]
 pub struct Process {
-    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// Always provided
     pid: u32,
-    status: String,
-    command: Vec<String>,
-    executable_path: String,
-    environment: Vec<String>,
-    working_directory: String,
-    root_directory: String,
-    used_memory_kB: u64,
-    virtual_memory_kB: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    executable_path: Option<String>,
+    /// Only provided when requested, values of variables that match the secret patterns are redacted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    working_directory: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    root_directory: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    used_memory_kB: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    virtual_memory_kB: Option<u64>,
+    /// Not available for processes without a parent
+    #[serde(skip_serializing_if = "Option::is_none")]
     parent_process: Option<u32>,
-    running_time: u64,
-    cpu_usage: f32,
-    disk_usage: DiskUsage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_time: Option<chrono::DateTime<chrono::Local>>,
+    /// Seconds since the process started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    running_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_usage: Option<f32>,
+    /// Cumulative CPU time spent in user mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_time_s: Option<f64>,
+    /// Cumulative CPU time spent in kernel mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_time_s: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disk_usage: Option<DiskUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scheduling: Option<process::Scheduling>,
+    /// cgroup v2 path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cgroup: Option<String>,
+    /// Id of the container that runs the process, detected from the cgroup path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    container_id: Option<String>,
+}
+
+impl Process {
+    // Information from sysinfo only, since it's done while holding the system lock
+    fn from(system: &sysSystem, process: &sysinfo::Process) -> Self {
+        let disk_usage = process.disk_usage();
+        Process {
+            name: Some(process.name().into()),
+            pid: process.pid().as_u32(),
+            user: Some(
+                process
+                    .user_id()
+                    .and_then(|user_id| system.get_user_by_id(user_id))
+                    .map(|user| user.name().into())
+                    .unwrap_or_default(),
+            ),
+            status: Some(format!("{:?}", process.status())),
+            command: Some(redact_command(&cli::args().secret_patterns, process.cmd())),
+            executable_path: Some(process.exe().to_str().unwrap_or_default().into()),
+            environment: None,
+            working_directory: Some(process.cwd().to_str().unwrap_or_default().into()),
+            root_directory: Some(process.root().to_str().unwrap_or_default().into()),
+            used_memory_kB: Some(process.memory()),
+            virtual_memory_kB: Some(process.virtual_memory()),
+            parent_process: process.parent().map(|pid| pid.as_u32()),
+            start_time: Some((UNIX_EPOCH + Duration::from_secs(process.start_time())).into()),
+            running_time: Some(process.run_time()),
+            cpu_usage: Some(process.cpu_usage()),
+            user_time_s: None,
+            system_time_s: None,
+            disk_usage: Some(DiskUsage {
+                total_written_bytes: disk_usage.total_written_bytes,
+                written_bytes: disk_usage.written_bytes,
+                total_read_bytes: disk_usage.total_read_bytes,
+                read_bytes: disk_usage.read_bytes,
+            }),
+            scheduling: None,
+            cgroup: None,
+            container_id: None,
+        }
+    }
+
+    // Information that is not provided by sysinfo and requires reading more files from /proc
+    fn add_details(&mut self) {
+        if let Some(((user_time_s, system_time_s), scheduling)) =
+            process::cpu_time_and_scheduling(self.pid)
+        {
+            self.user_time_s = Some(user_time_s);
+            self.system_time_s = Some(system_time_s);
+            self.scheduling = Some(scheduling);
+        }
+        self.cgroup = cgroup::process_cgroup(self.pid);
+        self.container_id = self.cgroup.as_deref().and_then(cgroup::container_id);
+    }
+
+    fn cpu_time(&self) -> f64 {
+        self.user_time_s.unwrap_or_default() + self.system_time_s.unwrap_or_default()
+    }
+
+    // Removes the fields that were not selected, pid is always kept
+    fn retain_fields(&mut self, fields: &[&str]) {
+        let keep = |field: &str| fields.contains(&field);
+        if !keep("name") {
+            self.name = None;
+        }
+        if !keep("user") {
+            self.user = None;
+        }
+        if !keep("status") {
+            self.status = None;
+        }
+        if !keep("command") {
+            self.command = None;
+        }
+        if !keep("executable_path") {
+            self.executable_path = None;
+        }
+        if !keep("environment") {
+            self.environment = None;
+        }
+        if !keep("working_directory") {
+            self.working_directory = None;
+        }
+        if !keep("root_directory") {
+            self.root_directory = None;
+        }
+        if !keep("used_memory_kB") {
+            self.used_memory_kB = None;
+        }
+        if !keep("virtual_memory_kB") {
+            self.virtual_memory_kB = None;
+        }
+        if !keep("parent_process") {
+            self.parent_process = None;
+        }
+        if !keep("start_time") {
+            self.start_time = None;
+        }
+        if !keep("running_time") {
+            self.running_time = None;
+        }
+        if !keep("cpu_usage") {
+            self.cpu_usage = None;
+        }
+        if !keep("user_time_s") {
+            self.user_time_s = None;
+        }
+        if !keep("system_time_s") {
+            self.system_time_s = None;
+        }
+        if !keep("disk_usage") {
+            self.disk_usage = None;
+        }
+        if !keep("scheduling") {
+            self.scheduling = None;
+        }
+        if !keep("cgroup") {
+            self.cgroup = None;
+        }
+        if !keep("container_id") {
+            self.container_id = None;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Apiv2Schema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessSort {
+    CpuUsage,
+    /// Total of user and system CPU time
+    CpuTime,
+    Memory,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Apiv2Schema)]
+pub struct ProcessFilter {
+    /// Case insensitive part of the process name
+    name: Option<String>,
+    /// Part of the executable path
+    executable_path: Option<String>,
+    user: Option<String>,
+    parent_pid: Option<u32>,
+    /// Case insensitive status, like Run or Sleep
+    status: Option<String>,
+    /// Sorts in descending order by cpu_usage, cpu_time or memory
+    sort: Option<ProcessSort>,
+    /// Maximum number of processes, applied after sorting
+    limit: Option<usize>,
+    /// Comma separated list of fields to be provided, e.g: "pid,name,cpu_usage", unknown fields are refused
+    #[serde(default, deserialize_with = "deserialize_process_fields")]
+    fields: Option<String>,
+    /// Provides the environment variables, with secret values redacted (default: false)
+    environment: Option<bool>,
+}
+
+fn deserialize_process_fields<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let fields = Option::<String>::deserialize(deserializer)?;
+    if let Some(fields) = &fields {
+        if let Some(field) = fields
+            .split(',')
+            .map(str::trim)
+            .find(|field| !PROCESS_FIELDS.contains(field))
+        {
+            return Err(serde::de::Error::custom(format!(
+                "unknown process field '{field}', valid fields are: {}",
+                PROCESS_FIELDS.join(", ")
+            )));
+        }
+    }
+    Ok(fields)
+}
+
+impl ProcessFilter {
+    fn matches(&self, process: &Process) -> bool {
+        let name = process.name.as_deref().unwrap_or_default();
+        let executable_path = process.executable_path.as_deref().unwrap_or_default();
+        let status = process.status.as_deref().unwrap_or_default();
+
+        self.name.as_ref().map_or(true, |filter| {
+            name.to_lowercase().contains(&filter.to_lowercase())
+        }) && self
+            .executable_path
+            .as_ref()
+            .map_or(true, |filter| executable_path.contains(filter.as_str()))
+            && self
+                .user
+                .as_ref()
+                .map_or(true, |filter| process.user.as_ref() == Some(filter))
+            && self.parent_pid.map_or(true, |parent_pid| {
+                process.parent_process == Some(parent_pid)
+            })
+            && self
+                .status
+                .as_ref()
+                .map_or(true, |filter| status.eq_ignore_ascii_case(filter))
+    }
+
+    fn fields(&self) -> Option<Vec<&str>> {
+        self.fields
+            .as_ref()
+            .map(|fields| fields.split(',').map(str::trim).collect())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct ProcessSummary {
+    pub name: String,
+    pub pid: u32,
+    pub user: String,
+    pub executable_path: String,
+    pub command: Vec<String>,
+}
+
+// Recursive types are not supported by the API schema generation
+#[derive(Clone, Debug, Serialize)]
+pub struct ProcessNode {
+    #[serde(flatten)]
+    process: Process,
+    /// Sum of the cpu usage of the process and all its descendants
+    subtree_cpu_usage: f32,
+    /// Sum of the used memory of the process and all its descendants
+    subtree_used_memory_kB: u64,
+    children: Vec<ProcessNode>,
 }
 
 #[derive(Clone, Debug, Serialize, Apiv2Schema)]
@@ -277,38 +579,295 @@ pub fn network() -> Vec<Network> {
         .collect::<Vec<Network>>()
 }
 
-#[cached(time = 5)]
-pub fn process() -> Vec<Process> {
+const REDACTED: &str = "<redacted>";
+
+// Case insensitive match where '*' matches any sequence of characters
+fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let text = text.to_lowercase();
+    let mut parts = pattern.split('*');
+    // There is always a first part, that is empty when the pattern starts with '*'
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match text.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    match parts.split_last() {
+        // No '*' in the pattern
+        None => rest.is_empty(),
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(position) => rest = &rest[position + part.len()..],
+                    None => return false,
+                }
+            }
+            rest.len() >= last.len() && rest.ends_with(last)
+        }
+    }
+}
+
+// Leading dashes of options are ignored, so "PASSWORD" also matches "--password"
+fn is_secret(patterns: &[String], name: &str) -> bool {
+    let name = name.trim_start_matches('-');
+    patterns
+        .iter()
+        .any(|pattern| matches_pattern(pattern.trim(), name))
+}
+
+// Redacts values of "NAME=value" variables when NAME matches a secret pattern
+fn redact_environment(patterns: &[String], environment: &[String]) -> Vec<String> {
+    environment
+        .iter()
+        .map(|variable| match variable.split_once('=') {
+            Some((name, _)) if is_secret(patterns, name) => format!("{name}={REDACTED}"),
+            _ => variable.clone(),
+        })
+        .collect()
+}
+
+// Redacts values of options that match a secret pattern,
+// in the forms "--password=value", "--password value" and "PASSWORD=value"
+fn redact_command(patterns: &[String], command: &[String]) -> Vec<String> {
+    let mut redact_next = false;
+    command
+        .iter()
+        .map(|argument| {
+            if redact_next {
+                redact_next = false;
+                return REDACTED.into();
+            }
+            match argument.split_once('=') {
+                Some((name, _)) if is_secret(patterns, name) => format!("{name}={REDACTED}"),
+                Some(_) => argument.clone(),
+                None => {
+                    redact_next = argument.starts_with('-') && is_secret(patterns, argument);
+                    argument.clone()
+                }
+            }
+        })
+        .collect()
+}
+
+// Processes with the information provided by sysinfo only, refreshed at most once per second,
+// so a sorted and limited list can be polled every second
+#[cached(time = 1)]
+fn process_snapshot() -> Vec<Process> {
     let mut system = SYSTEM.lock().unwrap();
     system.refresh_processes();
+    system.refresh_users_list();
     system
         .processes()
         .values()
+        .map(|process| Process::from(&system, process))
+        .collect::<Vec<Process>>()
+}
+
+#[cached(time = 5)]
+pub fn process() -> Vec<Process> {
+    let mut processes = process_snapshot();
+    for process in processes.iter_mut() {
+        process.add_details();
+    }
+    processes
+}
+
+// Provides a single process, refreshing only its information
+pub fn process_by_pid(pid: u32, environment: bool) -> Option<Process> {
+    let mut process = {
+        let mut system = SYSTEM.lock().unwrap();
+        let pid = Pid::from_u32(pid);
+        if !system.refresh_process(pid) {
+            return None;
+        }
+        system.refresh_users_list();
+        system.process(pid).map(|process| {
+            let mut result = Process::from(&system, process);
+            if environment {
+                result.environment = Some(redact_environment(
+                    &cli::args().secret_patterns,
+                    process.environ(),
+                ));
+            }
+            result
+        })?
+    };
+    process.add_details();
+    Some(process)
+}
+
+// Provides the environment of a process from the last refresh, with secret values redacted
+fn process_environment(pid: u32) -> Option<Vec<String>> {
+    let system = SYSTEM.lock().unwrap();
+    system
+        .process(Pid::from_u32(pid))
+        .map(|process| redact_environment(&cli::args().secret_patterns, process.environ()))
+}
+
+// Provides the cpu usage of each thread of a process, indexed by thread id
+pub fn thread_cpu_usage(pid: u32) -> HashMap<u32, f32> {
+    let system = SYSTEM.lock().unwrap();
+    system
+        .process(Pid::from_u32(pid))
+        .map(|process| {
+            process
+                .tasks
+                .iter()
+                .map(|(tid, task)| (tid.as_u32(), task.cpu_usage()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Details from /proc are only read for the processes and fields that are provided
+pub fn filtered_process(filter: &ProcessFilter) -> Vec<Process> {
+    let fields = filter.fields();
+    let needs_details = fields.as_ref().map_or(true, |fields| {
+        fields
+            .iter()
+            .any(|field| PROCESS_DETAIL_FIELDS.contains(field))
+    });
+
+    let mut processes: Vec<Process> = process_snapshot()
+        .into_iter()
+        .filter(|process| filter.matches(process))
+        .collect();
+
+    let sort_by_cpu_time = matches!(filter.sort, Some(ProcessSort::CpuTime));
+    if sort_by_cpu_time {
+        for process in processes.iter_mut() {
+            process.add_details();
+        }
+    }
+
+    match filter.sort {
+        Some(ProcessSort::CpuUsage) => processes.sort_by(|a, b| {
+            b.cpu_usage
+                .unwrap_or_default()
+                .total_cmp(&a.cpu_usage.unwrap_or_default())
+        }),
+        Some(ProcessSort::CpuTime) => {
+            processes.sort_by(|a, b| b.cpu_time().total_cmp(&a.cpu_time()))
+        }
+        Some(ProcessSort::Memory) => {
+            processes.sort_by(|a, b| b.used_memory_kB.cmp(&a.used_memory_kB))
+        }
+        None => {}
+    }
+
+    if let Some(limit) = filter.limit {
+        processes.truncate(limit);
+    }
+
+    if needs_details && !sort_by_cpu_time {
+        for process in processes.iter_mut() {
+            process.add_details();
+        }
+    }
+
+    if filter.environment.unwrap_or(false) {
+        for process in processes.iter_mut() {
+            process.environment = process_environment(process.pid);
+        }
+    }
+
+    if let Some(fields) = &fields {
+        for process in processes.iter_mut() {
+            process.retain_fields(fields);
+        }
+    }
+
+    processes
+}
+
+fn process_node(process: Process, children: &mut HashMap<u32, Vec<Process>>) -> ProcessNode {
+    let children: Vec<ProcessNode> = children
+        .remove(&process.pid)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|child| process_node(child, children))
+        .collect();
+
+    ProcessNode {
+        subtree_cpu_usage: process.cpu_usage.unwrap_or_default()
+            + children
+                .iter()
+                .map(|child| child.subtree_cpu_usage)
+                .sum::<f32>(),
+        subtree_used_memory_kB: process.used_memory_kB.unwrap_or_default()
+            + children
+                .iter()
+                .map(|child| child.subtree_used_memory_kB)
+                .sum::<u64>(),
+        process,
+        children,
+    }
+}
+
+// Provides the processes nested by parent, starting from root or from all processes without a known parent
+pub fn process_tree(root: Option<u32>) -> Result<Vec<ProcessNode>, String> {
+    let processes = process();
+    let pids: Vec<u32> = processes.iter().map(|process| process.pid).collect();
+
+    let mut roots = vec![];
+    let mut children: HashMap<u32, Vec<Process>> = HashMap::new();
+    for process in processes {
+        let is_root = match root {
+            Some(root) => process.pid == root,
+            None => process
+                .parent_process
+                .map_or(true, |parent| !pids.contains(&parent)),
+        };
+        if is_root {
+            roots.push(process);
+        } else if let Some(parent) = process.parent_process {
+            children.entry(parent).or_default().push(process);
+        }
+    }
+
+    if roots.is_empty() {
+        return Err(format!("Process {} not found.", root.unwrap_or_default()));
+    }
+
+    roots.sort_by_key(|process| process.pid);
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|process| process.pid);
+    }
+
+    Ok(roots
+        .into_iter()
+        .map(|root| process_node(root, &mut children))
+        .collect())
+}
+
+pub fn pids_by_name(name: &str) -> Vec<u32> {
+    let mut pids: Vec<u32> = process()
+        .into_iter()
+        .filter(|process| process.name.as_deref() == Some(name))
+        .map(|process| process.pid)
+        .collect();
+    pids.sort();
+    pids
+}
+
+pub fn process_summaries(pids: &[u32]) -> HashMap<u32, ProcessSummary> {
+    process()
+        .into_iter()
+        .filter(|process| pids.contains(&process.pid))
         .map(|process| {
-            let disk_usage = process.disk_usage();
-            Process {
-                name: process.name().into(),
-                pid: process.pid().as_u32(),
-                status: format!("{:?}", process.status()),
-                command: process.cmd().into(),
-                executable_path: process.exe().to_str().unwrap_or_default().into(),
-                environment: process.environ().into(),
-                working_directory: process.cwd().to_str().unwrap_or_default().into(),
-                root_directory: process.root().to_str().unwrap_or_default().into(),
-                used_memory_kB: process.memory(),
-                virtual_memory_kB: process.virtual_memory(),
-                parent_process: process.parent().and_then(|pid| Some(pid.as_u32())),
-                running_time: process.start_time(),
-                cpu_usage: process.cpu_usage(),
-                disk_usage: DiskUsage {
-                    total_written_bytes: disk_usage.total_written_bytes,
-                    written_bytes: disk_usage.written_bytes,
-                    total_read_bytes: disk_usage.total_read_bytes,
-                    read_bytes: disk_usage.read_bytes,
+            (
+                process.pid,
+                ProcessSummary {
+                    name: process.name.unwrap_or_default(),
+                    pid: process.pid,
+                    user: process.user.unwrap_or_default(),
+                    executable_path: process.executable_path.unwrap_or_default(),
+                    command: process.command.unwrap_or_default(),
                 },
-            }
+            )
         })
-        .collect::<Vec<Process>>()
+        .collect()
 }
 
 #[cached(time = 5)]
@@ -338,3 +897,174 @@ pub fn unix_time_seconds() -> u64 {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn default_patterns() -> Vec<String> {
+        strings(&["*TOKEN*", "*PASSWORD*", "*KEY*"])
+    }
+
+    #[test]
+    fn pattern_with_wildcards_matches_anywhere() {
+        assert!(matches_pattern("*TOKEN*", "TOKEN"));
+        assert!(matches_pattern("*TOKEN*", "GITHUB_TOKEN"));
+        assert!(matches_pattern("*TOKEN*", "token_file"));
+        assert!(matches_pattern("*TOKEN*", "my-Token-value"));
+        assert!(!matches_pattern("*TOKEN*", "TOKE"));
+        assert!(!matches_pattern("*TOKEN*", "PATH"));
+    }
+
+    #[test]
+    fn pattern_with_prefix_and_suffix() {
+        assert!(matches_pattern("AWS_*", "aws_secret_access_key"));
+        assert!(!matches_pattern("AWS_*", "MY_AWS_KEY"));
+        assert!(matches_pattern("*_KEY", "API_KEY"));
+        assert!(!matches_pattern("*_KEY", "API_KEY_FILE"));
+        assert!(matches_pattern("DB_*_PASS", "db_main_pass"));
+        assert!(!matches_pattern("DB_*_PASS", "DB_PASS"));
+        assert!(matches_pattern("*", "anything"));
+    }
+
+    #[test]
+    fn pattern_without_wildcard_matches_whole_name() {
+        assert!(matches_pattern("PASSWORD", "password"));
+        assert!(matches_pattern("password", "PASSWORD"));
+        assert!(!matches_pattern("PASSWORD", "PASSWORD_FILE"));
+        assert!(!matches_pattern("PASSWORD", "DB_PASSWORD"));
+    }
+
+    #[test]
+    fn command_option_with_equals_is_redacted() {
+        assert_eq!(
+            redact_command(
+                &default_patterns(),
+                &strings(&["app", "--password=x", "--port=80"])
+            ),
+            strings(&["app", "--password=<redacted>", "--port=80"])
+        );
+    }
+
+    #[test]
+    fn command_option_with_separate_value_is_redacted() {
+        assert_eq!(
+            redact_command(
+                &default_patterns(),
+                &strings(&["app", "--password", "x", "run"])
+            ),
+            strings(&["app", "--password", "<redacted>", "run"])
+        );
+    }
+
+    #[test]
+    fn command_variable_assignment_is_redacted() {
+        assert_eq!(
+            redact_command(
+                &default_patterns(),
+                &strings(&["env", "PASSWORD=x", "HOME=/root"])
+            ),
+            strings(&["env", "PASSWORD=<redacted>", "HOME=/root"])
+        );
+    }
+
+    #[test]
+    fn command_redaction_is_case_insensitive() {
+        assert_eq!(
+            redact_command(
+                &default_patterns(),
+                &strings(&["app", "--Api-Key", "x", "--DB-PassWord=y", "Github_Token=z"])
+            ),
+            strings(&[
+                "app",
+                "--Api-Key",
+                "<redacted>",
+                "--DB-PassWord=<redacted>",
+                "Github_Token=<redacted>"
+            ])
+        );
+    }
+
+    #[test]
+    fn command_option_as_last_argument_is_kept() {
+        assert_eq!(
+            redact_command(&default_patterns(), &strings(&["app", "--password"])),
+            strings(&["app", "--password"])
+        );
+    }
+
+    #[test]
+    fn command_with_pattern_without_wildcard() {
+        let patterns = strings(&["password"]);
+        assert_eq!(
+            redact_command(
+                &patterns,
+                &strings(&[
+                    "app",
+                    "--password",
+                    "x",
+                    "--password=y",
+                    "--db-password",
+                    "z"
+                ])
+            ),
+            strings(&[
+                "app",
+                "--password",
+                "<redacted>",
+                "--password=<redacted>",
+                "--db-password",
+                "z"
+            ])
+        );
+    }
+
+    #[test]
+    fn command_positional_arguments_are_kept() {
+        assert_eq!(
+            redact_command(
+                &default_patterns(),
+                &strings(&["cat", "token.txt", "-v", "password"])
+            ),
+            strings(&["cat", "token.txt", "-v", "password"])
+        );
+    }
+
+    #[test]
+    fn environment_is_redacted() {
+        assert_eq!(
+            redact_environment(
+                &default_patterns(),
+                &strings(&[
+                    "PASSWORD=x",
+                    "github_token=y",
+                    "HOME=/root",
+                    "EMPTY_KEY=",
+                    "NO_VALUE"
+                ])
+            ),
+            strings(&[
+                "PASSWORD=<redacted>",
+                "github_token=<redacted>",
+                "HOME=/root",
+                "EMPTY_KEY=<redacted>",
+                "NO_VALUE"
+            ])
+        );
+    }
+
+    #[test]
+    fn environment_with_pattern_without_wildcard() {
+        assert_eq!(
+            redact_environment(
+                &strings(&["PASSWORD"]),
+                &strings(&["password=x", "DB_PASSWORD=y"])
+            ),
+            strings(&["password=<redacted>", "DB_PASSWORD=y"])
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use paperclip::actix::Apiv2Schema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tracing::*;
+
+use crate::features::sysfs::{indexed_directories, name, read_number, read_string};
+
+const THERMAL_PATH: &str = "/sys/class/thermal";
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct TripPoint {
+    /// active, passive, hot or critical
+    #[serde(rename = "type")]
+    trip_type: String,
+    temperature: Option<f64>,
+    hysteresis: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct ThermalZone {
+    /// Name of the zone, like "thermal_zone0"
+    name: String,
+    /// Sensor type, like "cpu-thermal" or "x86_pkg_temp"
+    #[serde(rename = "type")]
+    zone_type: String,
+    /// Temperature in °C
+    temperature: Option<f64>,
+    /// Governor, like step_wise or power_allocator
+    policy: Option<String>,
+    /// enabled or disabled
+    mode: Option<String>,
+    trip_points: Vec<TripPoint>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct CoolingDevice {
+    /// Name of the device, like "cooling_device0"
+    name: String,
+    /// Device type, like "Processor" or "pwm-fan"
+    #[serde(rename = "type")]
+    device_type: String,
+    /// Current cooling state, from 0 (no cooling) to max_state
+    current_state: Option<u64>,
+    max_state: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Thermal {
+    zones: Vec<ThermalZone>,
+    cooling_devices: Vec<CoolingDevice>,
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct CoolingStateRequest {
+    /// Cooling state between 0 and max_state
+    state: u64,
+}
+
+// Temperatures are in millidegree Celsius
+fn read_temperature(path: &Path) -> Option<f64> {
+    read_number::<i64>(path).map(|temperature| temperature as f64 / 1000.0)
+}
+
+fn trip_points(path: &Path) -> Vec<TripPoint> {
+    (0..)
+        .map_while(|index| {
+            let trip_type = read_string(&path.join(format!("trip_point_{index}_type")))?;
+            Some(TripPoint {
+                trip_type,
+                temperature: read_temperature(&path.join(format!("trip_point_{index}_temp"))),
+                hysteresis: read_temperature(&path.join(format!("trip_point_{index}_hyst"))),
+            })
+        })
+        .collect()
+}
+
+fn zone(path: &Path) -> ThermalZone {
+    ThermalZone {
+        name: name(path),
+        zone_type: read_string(&path.join("type")).unwrap_or_default(),
+        temperature: read_temperature(&path.join("temp")),
+        policy: read_string(&path.join("policy")),
+        mode: read_string(&path.join("mode")),
+        trip_points: trip_points(path),
+    }
+}
+
+fn cooling_device(path: &Path) -> CoolingDevice {
+    CoolingDevice {
+        name: name(path),
+        device_type: read_string(&path.join("type")).unwrap_or_default(),
+        current_state: read_number(&path.join("cur_state")),
+        max_state: read_number(&path.join("max_state")),
+    }
+}
+
+pub fn thermal() -> Thermal {
+    Thermal {
+        zones: indexed_directories(THERMAL_PATH, "thermal_zone")
+            .iter()
+            .map(|(_, path)| zone(path))
+            .collect(),
+        cooling_devices: indexed_directories(THERMAL_PATH, "cooling_device")
+            .iter()
+            .map(|(_, path)| cooling_device(path))
+            .collect(),
+    }
+}
+
+pub fn set_cooling_state(
+    index: u32,
+    request: &CoolingStateRequest,
+) -> Result<CoolingDevice, String> {
+    let path = Path::new(THERMAL_PATH).join(format!("cooling_device{index}"));
+    if !path.is_dir() {
+        return Err(format!("Cooling device {index} not found."));
+    }
+
+    let device = cooling_device(&path);
+    if let Some(max_state) = device.max_state {
+        if request.state > max_state {
+            return Err(format!(
+                "Invalid state {}, it must be between 0 and {max_state}.",
+                request.state
+            ));
+        }
+    }
+
+    fs::write(path.join("cur_state"), request.state.to_string())
+        .map_err(|error| format!("Failed to set cooling device {index} state: {error}"))?;
+
+    info!(
+        "Cooling device {index} ({}) state changed to {}",
+        device.device_type, request.state
+    );
+    Ok(cooling_device(&path))
+}
@@ -0,0 +1,325 @@
+use chrono::{DateTime, Local, Utc};
+use paperclip::actix::Apiv2Schema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::*;
+
+use crate::features::sysfs::{name, read_string};
+
+const RTC_PATH: &str = "/sys/class/rtc";
+const LOCALTIME_PATH: &str = "/etc/localtime";
+const TIMEZONE_PATH: &str = "/etc/timezone";
+const ZONEINFO_PATH: &str = "/usr/share/zoneinfo";
+// Directories and files in zoneinfo that are not timezones
+const ZONEINFO_IGNORED: &[&str] = &["posix", "right", "posixrules", "localtime", "Factory"];
+// Touched by systemd-timesyncd on every successful synchronization, check systemd-timesyncd(8).
+// /var/lib/systemd/timesync/clock is not used, since it's also written without a synchronization
+const TIMESYNCD_SYNC_PATH: &str = "/run/systemd/timesync/synchronized";
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Synchronization {
+    /// False when the kernel clock is not synchronized (STA_UNSYNC), like before NTP sync
+    synchronized: bool,
+    /// TIME_OK, TIME_INS, TIME_DEL, TIME_OOP, TIME_WAIT or TIME_ERROR
+    clock_state: String,
+    /// Offset between the system clock and the reference clock
+    offset_us: f64,
+    estimated_error_us: i64,
+    maximum_error_us: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Rtc {
+    /// Name of the device, like "rtc0"
+    name: String,
+    /// Driver name, like "rtc_cmos" or "rtc-ds1307"
+    driver: String,
+    /// RTC time, usually kept in UTC
+    time: Option<DateTime<Utc>>,
+    /// RTC time minus system time
+    drift_s: Option<i64>,
+    /// True if this RTC was used to set the system clock at boot
+    system_clock_source: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Time {
+    time: DateTime<Local>,
+    /// Timezone name, like "America/Sao_Paulo"
+    timezone: Option<String>,
+    utc_offset_s: i32,
+    synchronization: Option<Synchronization>,
+    rtc: Vec<Rtc>,
+    /// Last synchronization done by systemd-timesyncd since boot, not available with other NTP clients,
+    /// like chrony or ntpd, use synchronization to check the clock state regardless of the client
+    timesyncd_last_sync: Option<DateTime<Local>>,
+    time_since_timesyncd_last_sync_s: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct SetTimeRequest {
+    /// New time, like "2024-03-01T12:00:00Z"
+    time: DateTime<Utc>,
+    /// The clock is only changed if the difference is at least this value (default: 0)
+    minimum_drift_s: Option<f64>,
+    /// Also write the new time to the hardware RTC with hwclock (default: false)
+    hardware_clock: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct SetTimeResult {
+    /// Requested time minus system time before the change
+    drift_s: f64,
+    /// False when the drift is smaller than minimum_drift_s
+    changed: bool,
+    hardware_clock_updated: bool,
+    time: Time,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Timezones {
+    current: Option<String>,
+    available: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct TimezoneRequest {
+    /// Timezone name, like "America/Sao_Paulo"
+    timezone: String,
+}
+
+// /etc/localtime is usually a link to the zoneinfo file, while some distributions also have /etc/timezone
+pub fn timezone() -> Option<String> {
+    fs::read_link(LOCALTIME_PATH)
+        .ok()
+        .and_then(|target| {
+            let target = target.to_string_lossy().to_string();
+            target
+                .find("zoneinfo/")
+                .map(|position| target[position + "zoneinfo/".len()..].to_string())
+        })
+        .or_else(|| read_string(Path::new(TIMEZONE_PATH)))
+}
+
+fn clock_state_name(state: libc::c_int) -> String {
+    match state {
+        libc::TIME_OK => "TIME_OK".into(),
+        libc::TIME_INS => "TIME_INS".into(),
+        libc::TIME_DEL => "TIME_DEL".into(),
+        libc::TIME_OOP => "TIME_OOP".into(),
+        libc::TIME_WAIT => "TIME_WAIT".into(),
+        libc::TIME_ERROR => "TIME_ERROR".into(),
+        _ => format!("Unknown({state})"),
+    }
+}
+
+// Reads the kernel clock discipline state without changing it
+fn synchronization() -> Option<Synchronization> {
+    let mut timex: libc::timex = unsafe { std::mem::zeroed() };
+    let state = unsafe { libc::adjtimex(&mut timex) };
+    if state < 0 {
+        warn!(
+            "Failed to read kernel clock state: {}",
+            std::io::Error::last_os_error()
+        );
+        return None;
+    }
+
+    // The offset is in nanoseconds when STA_NANO is set
+    let offset_us = if timex.status & libc::STA_NANO != 0 {
+        timex.offset as f64 / 1000.0
+    } else {
+        timex.offset as f64
+    };
+
+    Some(Synchronization {
+        synchronized: timex.status & libc::STA_UNSYNC == 0,
+        clock_state: clock_state_name(state),
+        offset_us,
+        estimated_error_us: timex.esterror as i64,
+        maximum_error_us: timex.maxerror as i64,
+    })
+}
+
+fn rtc_devices(now: SystemTime) -> Vec<Rtc> {
+    let entries = match fs::read_dir(RTC_PATH) {
+        Ok(entries) => entries,
+        // Many boards have no RTC at all
+        Err(_) => return vec![],
+    };
+
+    let now_s = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+
+    let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| {
+            let since_epoch = read_string(&path.join("since_epoch"))
+                .and_then(|seconds| seconds.parse::<u64>().ok());
+            Rtc {
+                name: name(path),
+                driver: read_string(&path.join("name")).unwrap_or_default(),
+                time: since_epoch.map(|seconds| (UNIX_EPOCH + Duration::from_secs(seconds)).into()),
+                drift_s: since_epoch.map(|seconds| seconds as i64 - now_s),
+                system_clock_source: read_string(&path.join("hctosys")).as_deref() == Some("1"),
+            }
+        })
+        .collect()
+}
+
+fn timesyncd_last_sync() -> Option<SystemTime> {
+    fs::metadata(TIMESYNCD_SYNC_PATH)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+pub fn time() -> Time {
+    let now = SystemTime::now();
+    let local: DateTime<Local> = now.into();
+    let last_sync = timesyncd_last_sync();
+
+    Time {
+        time: local,
+        timezone: timezone(),
+        utc_offset_s: local.offset().local_minus_utc(),
+        synchronization: synchronization(),
+        rtc: rtc_devices(now),
+        timesyncd_last_sync: last_sync.map(|last_sync| last_sync.into()),
+        time_since_timesyncd_last_sync_s: last_sync.and_then(|last_sync| {
+            now.duration_since(last_sync)
+                .ok()
+                .map(|elapsed| elapsed.as_secs())
+        }),
+    }
+}
+
+pub fn set_time(request: &SetTimeRequest) -> Result<SetTimeResult, String> {
+    let now = Utc::now();
+    let drift_s = (request.time - now).num_microseconds().unwrap_or(i64::MAX) as f64 / 1_000_000.0;
+    let minimum_drift_s = request.minimum_drift_s.unwrap_or_default();
+
+    if drift_s.abs() < minimum_drift_s {
+        return Ok(SetTimeResult {
+            drift_s,
+            changed: false,
+            hardware_clock_updated: false,
+            time: time(),
+        });
+    }
+
+    let timestamp = libc::timespec {
+        tv_sec: request.time.timestamp() as libc::time_t,
+        tv_nsec: request.time.timestamp_subsec_nanos() as libc::c_long,
+    };
+    if unsafe { libc::clock_settime(libc::CLOCK_REALTIME, &timestamp) } != 0 {
+        return Err(format!(
+            "Failed to set system time: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    info!(
+        "System time changed to {}, drift was {drift_s} s",
+        request.time
+    );
+
+    let hardware_clock_updated = if request.hardware_clock.unwrap_or(false) {
+        let output = Command::new("hwclock")
+            .arg("--systohc")
+            .output()
+            .map_err(|error| format!("Failed to run hwclock: {error}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "System time changed, but failed to update the hardware clock: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        true
+    } else {
+        false
+    };
+
+    Ok(SetTimeResult {
+        drift_s,
+        changed: true,
+        hardware_clock_updated,
+        time: time(),
+    })
+}
+
+// Timezone files start with the "TZif" magic number
+fn is_timezone_file(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    fs::File::open(path)
+        .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut magic))
+        .map(|_| &magic == b"TZif")
+        .unwrap_or(false)
+}
+
+fn find_timezones(root: &Path, directory: &Path, timezones: &mut Vec<String>) {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(error) => {
+            warn!("Failed to read {directory:?}: {error}");
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if ZONEINFO_IGNORED.contains(&name.as_str()) || name.contains('.') {
+            continue;
+        }
+        if path.is_dir() {
+            find_timezones(root, &path, timezones);
+        } else if is_timezone_file(&path) {
+            if let Ok(timezone) = path.strip_prefix(root) {
+                timezones.push(timezone.to_string_lossy().to_string());
+            }
+        }
+    }
+}
+
+pub fn timezones() -> Timezones {
+    let root = Path::new(ZONEINFO_PATH);
+    let mut available = vec![];
+    find_timezones(root, root, &mut available);
+    available.sort();
+
+    Timezones {
+        current: timezone(),
+        available,
+    }
+}
+
+pub fn set_timezone(request: &TimezoneRequest) -> Result<Timezones, String> {
+    let timezone = request.timezone.trim();
+    let valid_name = !timezone.is_empty()
+        && !timezone.starts_with('/')
+        && timezone
+            .split('/')
+            .all(|component| component != ".." && component != ".");
+    let zoneinfo = Path::new(ZONEINFO_PATH).join(timezone);
+    if !valid_name || !is_timezone_file(&zoneinfo) {
+        return Err(format!("Invalid timezone '{timezone}'."));
+    }
+
+    // Replaces the link atomically, so /etc/localtime is never missing
+    let temporary_path = format!("{LOCALTIME_PATH}.linux2rest");
+    let _ = fs::remove_file(&temporary_path);
+    std::os::unix::fs::symlink(&zoneinfo, &temporary_path)
+        .and_then(|_| fs::rename(&temporary_path, LOCALTIME_PATH))
+        .map_err(|error| format!("Failed to update {LOCALTIME_PATH}: {error}"))?;
+
+    if Path::new(TIMEZONE_PATH).exists() {
+        fs::write(TIMEZONE_PATH, format!("{timezone}\n"))
+            .map_err(|error| format!("Failed to write {TIMEZONE_PATH}: {error}"))?;
+    }
+
+    info!("Timezone changed to {timezone}");
+    Ok(timezones())
+}
@@ -0,0 +1,337 @@
+use chrono::{DateTime, Local};
+use paperclip::actix::Apiv2Schema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::*;
+
+const UTMP_PATH: &str = "/var/run/utmp";
+const WTMP_PATH: &str = "/var/log/wtmp";
+const BTMP_PATH: &str = "/var/log/btmp";
+
+// Size of struct utmp, check utmp(5)
+const RECORD_SIZE: usize = 384;
+
+// Record types, check utmp(5)
+const RUN_LVL: i16 = 1;
+const BOOT_TIME: i16 = 2;
+const USER_PROCESS: i16 = 7;
+const DEAD_PROCESS: i16 = 8;
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Session {
+    user: String,
+    /// Terminal, like "pts/0" for SSH or "ttyS0" for a serial console
+    tty: String,
+    /// Remote host, not available for local logins
+    host: Option<String>,
+    pid: i32,
+    login_time: DateTime<Local>,
+    /// Not available while the session is active
+    logout_time: Option<DateTime<Local>>,
+    /// True if the session ended by a reboot or shutdown, instead of a logout
+    ended_by_shutdown: bool,
+    /// Time until logout, or until now for active sessions
+    duration_s: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct FailedLogin {
+    user: String,
+    tty: String,
+    host: Option<String>,
+    time: DateTime<Local>,
+}
+
+#[derive(Clone, Debug, Serialize, Apiv2Schema)]
+pub struct Users {
+    /// Active sessions, from utmp
+    sessions: Vec<Session>,
+    /// Login history, most recent first, from wtmp
+    last_logins: Vec<Session>,
+    /// Failed login attempts, most recent first, from btmp (only readable by root).
+    /// Only provided to requests with the authentication token, since users may type their password as username
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failed_logins: Option<Vec<FailedLogin>>,
+}
+
+struct Record {
+    kind: i16,
+    pid: i32,
+    line: String,
+    user: String,
+    host: String,
+    time: SystemTime,
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
+// Record layout: type (i16 + padding), pid (i32), line[32], id[4], user[32], host[256],
+// exit status (2 x i16), session (i32), time (i32 seconds + i32 microseconds), address[16] and unused[20]
+fn parse_record(bytes: &[u8]) -> Record {
+    let i16_at = |offset: usize| i16::from_ne_bytes(bytes[offset..offset + 2].try_into().unwrap());
+    let i32_at = |offset: usize| i32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+    let seconds = i32_at(340).max(0) as u64;
+    let microseconds = i32_at(344).max(0) as u64;
+    Record {
+        kind: i16_at(0),
+        pid: i32_at(4),
+        line: c_string(&bytes[8..40]),
+        user: c_string(&bytes[44..76]),
+        host: c_string(&bytes[76..332]),
+        time: UNIX_EPOCH + Duration::from_secs(seconds) + Duration::from_micros(microseconds),
+    }
+}
+
+fn records(path: &str) -> Vec<Record> {
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        // btmp is only readable by root, so this is expected
+        Err(error) => {
+            debug!("Failed to read {path}: {error}");
+            return vec![];
+        }
+    };
+
+    content
+        .chunks_exact(RECORD_SIZE)
+        .map(parse_record)
+        .collect()
+}
+
+fn host(record: &Record) -> Option<String> {
+    Some(record.host.clone()).filter(|host| !host.is_empty())
+}
+
+fn session(record: &Record, logout: Option<(SystemTime, bool)>, now: SystemTime) -> Session {
+    let end = logout.map_or(now, |(time, _)| time);
+    Session {
+        user: record.user.clone(),
+        tty: record.line.clone(),
+        host: host(record),
+        pid: record.pid,
+        login_time: record.time.into(),
+        logout_time: logout.map(|(time, _)| time.into()),
+        ended_by_shutdown: matches!(logout, Some((_, true))),
+        duration_s: end
+            .duration_since(record.time)
+            .unwrap_or_default()
+            .as_secs(),
+    }
+}
+
+// utmp may keep entries of processes that died without cleaning it
+fn is_alive(pid: i32) -> bool {
+    Path::new(&format!("/proc/{pid}")).exists()
+}
+
+pub fn sessions() -> Vec<Session> {
+    let now = SystemTime::now();
+    records(UTMP_PATH)
+        .iter()
+        .filter(|record| record.kind == USER_PROCESS && is_alive(record.pid))
+        .map(|record| session(record, None, now))
+        .collect()
+}
+
+// Like last(1), wtmp is read from the end, so each login is matched with the
+// logout that came after it on the same terminal, or with the next shutdown
+pub fn last_logins(limit: usize) -> Vec<Session> {
+    sessions_from_history(&records(WTMP_PATH), limit, SystemTime::now())
+}
+
+fn sessions_from_history(records: &[Record], limit: usize, now: SystemTime) -> Vec<Session> {
+    let mut logouts: HashMap<String, SystemTime> = HashMap::new();
+    let mut shutdown: Option<SystemTime> = None;
+    let mut sessions = vec![];
+
+    for record in records.iter().rev() {
+        if sessions.len() >= limit {
+            break;
+        }
+        match record.kind {
+            USER_PROCESS => {
+                let logout = match logouts.remove(&record.line) {
+                    Some(time) => Some((time, false)),
+                    None => shutdown.map(|time| (time, true)),
+                };
+                sessions.push(session(record, logout, now));
+            }
+            DEAD_PROCESS if !record.line.is_empty() => {
+                logouts.insert(record.line.clone(), record.time);
+            }
+            BOOT_TIME => {
+                shutdown = Some(record.time);
+                logouts.clear();
+            }
+            RUN_LVL if record.user == "shutdown" => {
+                shutdown = Some(record.time);
+                logouts.clear();
+            }
+            _ => {}
+        }
+    }
+
+    sessions
+}
+
+pub fn failed_logins(limit: usize) -> Vec<FailedLogin> {
+    records(BTMP_PATH)
+        .iter()
+        .rev()
+        .take(limit)
+        .map(|record| FailedLogin {
+            user: record.user.clone(),
+            tty: record.line.clone(),
+            host: host(record),
+            time: record.time.into(),
+        })
+        .collect()
+}
+
+pub fn users(limit: usize, include_failed_logins: bool) -> Users {
+    Users {
+        sessions: sessions(),
+        last_logins: last_logins(limit),
+        failed_logins: include_failed_logins.then(|| failed_logins(limit)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 10_000;
+
+    fn record(kind: i16, line: &str, user: &str, seconds: i32) -> Record {
+        let mut bytes = vec![0u8; RECORD_SIZE];
+        bytes[0..2].copy_from_slice(&kind.to_ne_bytes());
+        bytes[4..8].copy_from_slice(&1234i32.to_ne_bytes());
+        bytes[8..8 + line.len()].copy_from_slice(line.as_bytes());
+        bytes[44..44 + user.len()].copy_from_slice(user.as_bytes());
+        bytes[76..76 + 9].copy_from_slice(b"10.0.0.1\0");
+        bytes[340..344].copy_from_slice(&seconds.to_ne_bytes());
+        parse_record(&bytes)
+    }
+
+    fn history(records: &[Record]) -> Vec<Session> {
+        sessions_from_history(records, 100, UNIX_EPOCH + Duration::from_secs(NOW))
+    }
+
+    fn logout_time(session: &Session) -> Option<i64> {
+        session.logout_time.map(|time| time.timestamp())
+    }
+
+    #[test]
+    fn parses_record_fields() {
+        let record = record(USER_PROCESS, "pts/0", "pi", 100);
+        assert_eq!(record.kind, USER_PROCESS);
+        assert_eq!(record.pid, 1234);
+        assert_eq!(record.line, "pts/0");
+        assert_eq!(record.user, "pi");
+        assert_eq!(record.host, "10.0.0.1");
+        assert_eq!(record.time, UNIX_EPOCH + Duration::from_secs(100));
+    }
+
+    #[test]
+    fn logout_on_the_same_tty_ends_the_session() {
+        let sessions = history(&[
+            record(USER_PROCESS, "pts/0", "pi", 100),
+            record(USER_PROCESS, "pts/1", "root", 150),
+            record(DEAD_PROCESS, "pts/0", "", 200),
+        ]);
+
+        assert_eq!(sessions.len(), 2);
+        // Most recent first
+        assert_eq!(sessions[0].user, "root");
+        assert_eq!(logout_time(&sessions[0]), None);
+        assert_eq!(sessions[0].duration_s, NOW - 150);
+        assert_eq!(sessions[1].user, "pi");
+        assert_eq!(logout_time(&sessions[1]), Some(200));
+        assert!(!sessions[1].ended_by_shutdown);
+        assert_eq!(sessions[1].duration_s, 100);
+    }
+
+    #[test]
+    fn logout_only_ends_the_latest_session_of_the_tty() {
+        let sessions = history(&[
+            record(USER_PROCESS, "pts/0", "pi", 100),
+            record(DEAD_PROCESS, "pts/0", "", 200),
+            record(USER_PROCESS, "pts/0", "pi", 300),
+        ]);
+
+        assert_eq!(logout_time(&sessions[0]), None);
+        assert_eq!(logout_time(&sessions[1]), Some(200));
+    }
+
+    #[test]
+    fn boot_ends_all_open_sessions() {
+        let sessions = history(&[
+            record(USER_PROCESS, "pts/0", "pi", 100),
+            record(USER_PROCESS, "tty1", "root", 110),
+            record(BOOT_TIME, "~", "reboot", 500),
+            record(USER_PROCESS, "pts/0", "pi", 600),
+        ]);
+
+        assert_eq!(sessions.len(), 3);
+        assert_eq!(logout_time(&sessions[0]), None);
+        for session in &sessions[1..] {
+            assert_eq!(logout_time(session), Some(500));
+            assert!(session.ended_by_shutdown);
+        }
+    }
+
+    #[test]
+    fn shutdown_run_level_ends_all_open_sessions() {
+        let sessions = history(&[
+            record(USER_PROCESS, "pts/0", "pi", 100),
+            record(RUN_LVL, "~", "shutdown", 400),
+            record(BOOT_TIME, "~", "reboot", 500),
+        ]);
+
+        assert_eq!(logout_time(&sessions[0]), Some(400));
+        assert!(sessions[0].ended_by_shutdown);
+    }
+
+    #[test]
+    fn logout_before_shutdown_is_not_a_shutdown() {
+        let sessions = history(&[
+            record(USER_PROCESS, "pts/0", "pi", 100),
+            record(DEAD_PROCESS, "pts/0", "", 200),
+            record(BOOT_TIME, "~", "reboot", 500),
+        ]);
+
+        assert_eq!(logout_time(&sessions[0]), Some(200));
+        assert!(!sessions[0].ended_by_shutdown);
+    }
+
+    #[test]
+    fn other_run_levels_are_ignored() {
+        let sessions = history(&[
+            record(USER_PROCESS, "pts/0", "pi", 100),
+            record(RUN_LVL, "~", "runlevel", 400),
+        ]);
+
+        assert_eq!(logout_time(&sessions[0]), None);
+    }
+
+    #[test]
+    fn limit_is_applied() {
+        let records: Vec<Record> = (0..5)
+            .map(|index| record(USER_PROCESS, "pts/0", "pi", index * 10))
+            .collect();
+        let sessions = sessions_from_history(&records, 2, UNIX_EPOCH + Duration::from_secs(NOW));
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].login_time.timestamp(), 40);
+    }
+}
@@ -27,11 +27,14 @@ pub fn start() {
 
                 match category {
                     cli::LogSetting::Netstat => {
-                        print(category, features::netstat::netstat());
+                        print(category, features::netstat::netstat(&Default::default()));
                     }
                     cli::LogSetting::Platform => {
                         print(category, features::platform::platform());
                     }
+                    cli::LogSetting::PowerSupply => {
+                        print(category, features::power_supply::power_supply());
+                    }
                     cli::LogSetting::SerialPorts => {
                         print(category, features::serial::serial(None));
                     }
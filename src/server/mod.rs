@@ -21,31 +21,118 @@ pub fn run(server_address: &str) {
                 r"/{filename:.*(\.html|\.js|\.css)}",
                 web::get().to(pages::root),
             )
+            .route("/dns", web::get().to(pages::dns))
+            .route("/dns/hostname", web::get().to(pages::dns_hostname))
+            .route("/dns/hostname", web::post().to(pages::set_dns_hostname))
+            .route(
+                "/docker/containers",
+                web::get().to(pages::docker_containers),
+            )
+            .route(
+                "/docker/containers/{container}/logs",
+                web::get().to(pages::docker_container_logs),
+            )
+            .route(
+                "/docker/containers/{container}/stats",
+                web::get().to(pages::docker_container_stats),
+            )
             .route("/kernel_buffer", web::get().to(pages::kernel_buffer))
             .route("/model", web::get().to(pages::model))
             .route("/netstat", web::get().to(pages::netstat))
+            .route(
+                "/netstat/listening",
+                web::get().to(pages::netstat_listening),
+            )
+            .route("/netstat/port/{port}", web::get().to(pages::netstat_port))
+            .route("/netstat/stats", web::get().to(pages::netstat_stats))
+            .route("/netstat/unix", web::get().to(pages::netstat_unix))
             .route("/platform", web::get().to(pages::platform))
             .route("/serial", web::get().to(pages::serial))
             .route("/system", web::get().to(pages::system))
+            .route("/system/cgroups", web::get().to(pages::system_cgroups))
             .route("/system/cpu", web::get().to(pages::system_cpu))
+            .route(
+                "/system/cpu/frequency",
+                web::get().to(pages::system_cpu_frequency),
+            )
+            .route(
+                "/system/cpu/frequency/{policy}",
+                web::post().to(pages::set_system_cpu_frequency_policy),
+            )
             .route("/system/disk", web::get().to(pages::system_disk))
+            .route("/system/hwmon", web::get().to(pages::system_hwmon))
             .route("/system/info", web::get().to(pages::system_info))
+            .route(
+                "/system/interrupts",
+                web::get().to(pages::system_interrupts),
+            )
+            .route(
+                "/system/interrupts/{irq}/smp_affinity",
+                web::post().to(pages::set_system_interrupt_smp_affinity),
+            )
+            .route("/system/kernel", web::get().to(pages::system_kernel))
             .route("/system/memory", web::get().to(pages::system_memory))
             .route("/system/network", web::get().to(pages::system_network))
+            .route(
+                "/system/power_supply",
+                web::get().to(pages::system_power_supply),
+            )
             .route("/system/process", web::get().to(pages::system_process))
+            .route(
+                "/system/process/signal",
+                web::post().to(pages::system_process_signal),
+            )
+            .route(
+                "/system/process/tree",
+                web::get().to(pages::system_process_tree),
+            )
+            .route(
+                "/system/process/{pid}",
+                web::get().to(pages::system_process_pid),
+            )
+            .route(
+                "/system/process/{pid}/scheduling",
+                web::get().to(pages::system_process_scheduling),
+            )
+            .route(
+                "/system/process/{pid}/scheduling",
+                web::post().to(pages::set_system_process_scheduling),
+            )
+            .route("/system/sysctl", web::get().to(pages::system_sysctl))
+            .route("/system/sysctl", web::post().to(pages::set_system_sysctl))
             .route(
                 "/system/temperature",
                 web::get().to(pages::system_temperature),
             )
+            .route("/system/thermal", web::get().to(pages::system_thermal))
+            .route(
+                "/system/thermal/cooling_devices/{index}",
+                web::post().to(pages::set_system_thermal_cooling_device),
+            )
+            .route("/system/time", web::get().to(pages::system_time))
+            .route("/system/time", web::post().to(pages::set_system_time))
+            .route(
+                "/system/time/timezone",
+                web::get().to(pages::system_time_timezone),
+            )
+            .route(
+                "/system/time/timezone",
+                web::post().to(pages::set_system_time_timezone),
+            )
             .route(
                 "/system/unix_time_seconds",
                 web::get().to(pages::system_unix_time_seconds),
             )
+            .route("/system/users", web::get().to(pages::system_users))
             .route("/udev", web::get().to(pages::udev))
             .route(
                 "/ws/kernel_buffer",
                 web::get().to(pages::websocket_kernel_buffer),
             )
+            .route(
+                "/ws/power_supply",
+                web::get().to(pages::websocket_power_supply),
+            )
             .build()
     })
     .bind(server_address)
@@ -1,4 +1,6 @@
 use actix_web::{
+    error::BlockingError,
+    http::header,
     web::{self, Json},
     HttpRequest, HttpResponse,
 };
@@ -8,6 +10,7 @@ use paperclip::actix::Apiv2Schema;
 use serde::Deserialize;
 use tracing::*;
 
+use crate::cli;
 use crate::features;
 
 pub fn load_file(file_name: &str) -> String {
@@ -38,19 +41,182 @@ pub fn root(req: HttpRequest) -> HttpResponse {
         .body(path)
 }
 
+// Runs functions that block for a long time, like Docker requests, outside of the server workers
+async fn blocking<T, F>(function: F) -> Result<T, String>
+where
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+    T: Send + 'static,
+{
+    web::block(function).await.map_err(|error| match error {
+        BlockingError::Error(error) => error,
+        BlockingError::Canceled => "Blocking operation was canceled.".into(),
+    })
+}
+
+// Compares all bytes, so the time taken doesn't reveal how much of the token matches
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b)
+        .fold(0u8, |difference, (a, b)| difference | (a ^ b))
+        == 0
+}
+
+fn has_token(req: &HttpRequest, token: &str) -> bool {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map_or(false, |authorization| {
+            constant_time_eq(authorization.as_bytes(), token.as_bytes())
+        })
+}
+
+// Like authorize, for endpoints that only hide sensitive information from requests without the token
+fn is_authorized(req: &HttpRequest) -> bool {
+    cli::args()
+        .as_ref()
+        .auth_token
+        .as_ref()
+        .map_or(false, |token| has_token(req, token))
+}
+
+// Endpoints that change the system should call it before doing anything,
+// and should not log the request, since its headers have the token
+fn authorize(req: &HttpRequest) -> Result<(), HttpResponse> {
+    let token = match &cli::args().as_ref().auth_token {
+        Some(token) => token.clone(),
+        None => {
+            return Err(HttpResponse::Forbidden()
+                .content_type("text/plain")
+                .body("error: Endpoint disabled, no authentication token was configured."))
+        }
+    };
+
+    if !has_token(req, &token) {
+        warn!("Unauthorized request: {} {}", req.method(), req.path());
+        return Err(HttpResponse::Unauthorized()
+            .content_type("text/plain")
+            .body("error: Invalid or missing authentication token."));
+    }
+
+    Ok(())
+}
+
 #[derive(Deserialize, Apiv2Schema)]
 pub struct KernelBufferQuery {
     start: Option<usize>,
     size: Option<usize>,
 }
 
+#[api_v2_operation]
+/// Provides DNS resolver configuration, hosts entries and hostname
+pub async fn dns(req: HttpRequest) -> Json<features::dns::Dns> {
+    debug!("{} {}", req.method(), req.path());
+
+    Json(features::dns::dns())
+}
+
+#[api_v2_operation]
+/// Provides static and transient hostname
+pub async fn dns_hostname(req: HttpRequest) -> Json<features::dns::Hostname> {
+    debug!("{} {}", req.method(), req.path());
+
+    Json(features::dns::hostname())
+}
+
+#[api_v2_operation]
+/// Changes the hostname, requires authentication
+pub async fn set_dns_hostname(
+    req: HttpRequest,
+    json: web::Json<features::dns::HostnameRequest>,
+) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &json);
+
+    if let Err(response) = authorize(&req) {
+        return response;
+    }
+
+    match features::dns::set_hostname(&json.into_inner()) {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::BadRequest()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
+#[api_v2_operation]
+/// Provides Docker containers with image, state, health, restart count and ports
+pub async fn docker_containers(req: HttpRequest) -> HttpResponse {
+    debug!("{} {}", req.method(), req.path());
+
+    match blocking(features::docker::containers).await {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::InternalServerError()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
+#[api_v2_operation]
+/// Provides CPU and memory usage of a Docker container
+pub async fn docker_container_stats(
+    req: HttpRequest,
+    container: web::Path<String>,
+) -> HttpResponse {
+    debug!("{} {}", req.method(), req.path());
+
+    let container = container.into_inner();
+    match blocking(move || features::docker::stats(&container)).await {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::InternalServerError()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct DockerLogsQuery {
+    /// Number of lines from the end of the logs (default: 100)
+    lines: Option<usize>,
+}
+
+#[api_v2_operation]
+/// Provides the last log lines of a Docker container
+pub async fn docker_container_logs(
+    req: HttpRequest,
+    container: web::Path<String>,
+    query: web::Query<DockerLogsQuery>,
+) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &query);
+
+    let container = container.into_inner();
+    let lines = query.lines.unwrap_or(100);
+    match blocking(move || features::docker::logs(&container, lines)).await {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::InternalServerError()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
 #[api_v2_operation]
 /// Provides kernel information, like dmesg
 pub fn kernel_buffer(
     req: HttpRequest,
     query: web::Query<KernelBufferQuery>,
 ) -> Json<Vec<features::kernel::KernelMessage>> {
-    debug!("{:#?}", req);
+    debug!("{} {}", req.method(), req.path());
 
     let query = query.into_inner();
 
@@ -58,11 +224,62 @@ pub fn kernel_buffer(
 }
 
 #[api_v2_operation]
-/// Provides the same output as netstat: TCP/UDP ports that are in use and who is using it
-pub fn netstat(req: HttpRequest) -> Json<features::netstat::Netstat> {
-    debug!("{:#?}", req);
+/// Provides the same output as netstat: TCP/UDP ports and unix sockets that are in use and who is using it
+pub fn netstat(
+    req: HttpRequest,
+    query: web::Query<features::netstat::Filter>,
+) -> Json<features::netstat::Netstat> {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &query);
 
-    Json(features::netstat::netstat())
+    Json(features::netstat::netstat(&query.into_inner()))
+}
+
+#[api_v2_operation]
+/// Provides unix domain sockets, their peers and who is using them
+pub fn netstat_unix(
+    req: HttpRequest,
+    query: web::Query<features::netstat::Filter>,
+) -> Json<Vec<features::netstat::unix::Unix>> {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &query);
+
+    Json(features::netstat::unix(&query.into_inner()))
+}
+
+#[api_v2_operation]
+/// Provides kernel network protocol statistics: TCP retransmits, UDP drops, socket memory and etc
+pub fn netstat_stats(req: HttpRequest) -> Json<features::netstat::stats::Stats> {
+    debug!("{} {}", req.method(), req.path());
+
+    Json(features::netstat::stats::stats())
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct NetstatListeningQuery {
+    /// Embeds name, executable, user and command line of the processes using each service
+    processes: Option<bool>,
+}
+
+#[api_v2_operation]
+/// Provides listening TCP and bound UDP sockets grouped by service
+pub fn netstat_listening(
+    req: HttpRequest,
+    query: web::Query<NetstatListeningQuery>,
+) -> Json<Vec<features::netstat::ListeningService>> {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &query);
+
+    let query = query.into_inner();
+
+    Json(features::netstat::listening(
+        query.processes.unwrap_or(false),
+    ))
+}
+
+#[api_v2_operation]
+/// Provides the TCP/UDP sockets bound to a local port and the processes that own them
+pub fn netstat_port(req: HttpRequest, port: web::Path<u16>) -> Json<features::netstat::Netstat> {
+    debug!("{} {}", req.method(), req.path());
+
+    Json(features::netstat::port(port.into_inner()))
 }
 
 #[derive(Debug, Deserialize, Apiv2Schema)]
@@ -76,7 +293,7 @@ pub async fn serial(
     req: HttpRequest,
     query: web::Query<SerialQuery>,
 ) -> Json<features::serial::SerialPorts> {
-    debug!("{:#?}, {:#?}", req, &query);
+    debug!("{} {}, {:#?}", req.method(), req.path(), &query);
 
     let query = query.into_inner();
 
@@ -86,23 +303,124 @@ pub async fn serial(
 #[api_v2_operation]
 /// Provides system information: cpu, disk, operating system, memory, network, processes, sensors
 pub async fn system(req: HttpRequest) -> Json<features::system::System> {
-    debug!("{:#?}", req);
+    debug!("{} {}", req.method(), req.path());
 
     Json(features::system::system())
 }
 
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct CgroupsQuery {
+    /// Path of the cgroup, like "/system.slice", its descendants are also provided
+    path: Option<String>,
+}
+
+#[api_v2_operation]
+/// Provides cgroup v2 resource usage: cpu, memory, OOM events, io and number of processes
+pub async fn system_cgroups(req: HttpRequest, query: web::Query<CgroupsQuery>) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &query);
+
+    match features::cgroup::cgroups(query.into_inner().path) {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::NotFound()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
+#[api_v2_operation]
+/// Provides hardware monitoring sensors: temperature, voltage, current, power, energy, fan and humidity
+pub async fn system_hwmon(req: HttpRequest) -> Json<Vec<features::hwmon::Chip>> {
+    debug!("{} {}", req.method(), req.path());
+
+    Json(features::hwmon::hwmon())
+}
+
+#[api_v2_operation]
+/// Provides thermal zones with their trip points, and cooling devices state
+pub async fn system_thermal(req: HttpRequest) -> Json<features::thermal::Thermal> {
+    debug!("{} {}", req.method(), req.path());
+
+    Json(features::thermal::thermal())
+}
+
+#[api_v2_operation]
+/// Changes the state of a cooling device, like a fan speed, when allowed by the kernel
+pub async fn set_system_thermal_cooling_device(
+    req: HttpRequest,
+    index: web::Path<u32>,
+    json: web::Json<features::thermal::CoolingStateRequest>,
+) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &json);
+
+    if let Err(response) = authorize(&req) {
+        return response;
+    }
+
+    match features::thermal::set_cooling_state(index.into_inner(), &json.into_inner()) {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::BadRequest()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
+#[api_v2_operation]
+/// Provides power supplies and batteries: status, capacity, voltage, current, energy and health
+pub async fn system_power_supply(
+    req: HttpRequest,
+) -> Json<Vec<features::power_supply::PowerSupply>> {
+    debug!("{} {}", req.method(), req.path());
+
+    Json(features::power_supply::power_supply())
+}
+
 #[api_v2_operation]
 /// Provides system information for cpu only
 pub async fn system_cpu(req: HttpRequest) -> Json<Vec<features::system::Cpu>> {
-    debug!("{:#?}", req);
+    debug!("{} {}", req.method(), req.path());
 
     Json(features::system::cpu())
 }
 
+#[api_v2_operation]
+/// Provides cpu frequency scaling policies, time in each frequency and thermal throttling counters
+pub async fn system_cpu_frequency(req: HttpRequest) -> Json<features::cpufreq::CpuFrequency> {
+    debug!("{} {}", req.method(), req.path());
+
+    Json(features::cpufreq::frequency())
+}
+
+#[api_v2_operation]
+/// Changes the governor and frequency limits of a cpu frequency scaling policy
+pub async fn set_system_cpu_frequency_policy(
+    req: HttpRequest,
+    index: web::Path<u32>,
+    json: web::Json<features::cpufreq::FrequencyPolicyRequest>,
+) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &json);
+
+    if let Err(response) = authorize(&req) {
+        return response;
+    }
+
+    match features::cpufreq::set_policy(index.into_inner(), &json.into_inner()) {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::BadRequest()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
 #[api_v2_operation]
 /// Provides system information for disk only
 pub async fn system_disk(req: HttpRequest) -> Json<Vec<features::system::Disk>> {
-    debug!("{:#?}", req);
+    debug!("{} {}", req.method(), req.path());
 
     Json(features::system::disk())
 }
@@ -110,15 +428,75 @@ pub async fn system_disk(req: HttpRequest) -> Json<Vec<features::system::Disk>>
 #[api_v2_operation]
 /// Provides system information from operating system only
 pub async fn system_info(req: HttpRequest) -> Json<features::system::OsInfo> {
-    debug!("{:#?}", req);
+    debug!("{} {}", req.method(), req.path());
 
     Json(features::system::info())
 }
 
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct UsersQuery {
+    /// Maximum number of last logins and failed logins (default: 100)
+    limit: Option<usize>,
+}
+
+#[api_v2_operation]
+/// Provides logged in users, login history and failed login attempts.
+/// Failed login attempts require the "Authorization: Bearer <token>" header, since they may have passwords
+pub async fn system_users(
+    req: HttpRequest,
+    query: web::Query<UsersQuery>,
+) -> Json<features::users::Users> {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &query);
+
+    Json(features::users::users(
+        query.limit.unwrap_or(100),
+        is_authorized(&req),
+    ))
+}
+
+#[api_v2_operation]
+/// Provides hardware interrupts and softirqs per CPU, with rates over the last second
+pub async fn system_interrupts(req: HttpRequest) -> Json<features::interrupts::Interrupts> {
+    debug!("{} {}", req.method(), req.path());
+
+    Json(features::interrupts::interrupts())
+}
+
+#[api_v2_operation]
+/// Changes the CPUs allowed to handle an IRQ
+pub async fn set_system_interrupt_smp_affinity(
+    req: HttpRequest,
+    irq: web::Path<u32>,
+    json: web::Json<features::interrupts::AffinityRequest>,
+) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &json);
+
+    if let Err(response) = authorize(&req) {
+        return response;
+    }
+
+    match features::interrupts::set_smp_affinity(irq.into_inner(), &json.into_inner()) {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::BadRequest()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
+#[api_v2_operation]
+/// Provides kernel command line, taint flags, loaded modules and build configuration
+pub async fn system_kernel(req: HttpRequest) -> Json<features::kernel_info::KernelInfo> {
+    debug!("{} {}", req.method(), req.path());
+
+    Json(features::kernel_info::kernel_info())
+}
+
 #[api_v2_operation]
 /// Provides system information for memory only
 pub async fn system_memory(req: HttpRequest) -> Json<features::system::Memory> {
-    debug!("{:#?}", req);
+    debug!("{} {}", req.method(), req.path());
 
     Json(features::system::memory())
 }
@@ -126,31 +504,249 @@ pub async fn system_memory(req: HttpRequest) -> Json<features::system::Memory> {
 #[api_v2_operation]
 /// Provides system information for network only
 pub async fn system_network(req: HttpRequest) -> Json<Vec<features::system::Network>> {
-    debug!("{:#?}", req);
+    debug!("{} {}", req.method(), req.path());
 
     Json(features::system::network())
 }
 
 #[api_v2_operation]
-/// Provides system information for processes only
-pub async fn system_process(req: HttpRequest) -> Json<Vec<features::system::Process>> {
-    debug!("{:#?}", req);
+/// Provides system information for processes only, with optional filtering, sorting and field selection.
+/// The list is refreshed at most once per second, and /proc details are only read for the provided processes
+pub async fn system_process(
+    req: HttpRequest,
+    query: web::Query<features::system::ProcessFilter>,
+) -> Json<Vec<features::system::Process>> {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &query);
+
+    Json(features::system::filtered_process(&query.into_inner()))
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct ProcessTreeQuery {
+    /// Pid of the process used as root, all processes are provided if not defined
+    root: Option<u32>,
+}
+
+#[api_v2_operation]
+/// Provides the processes nested by parent, with cpu usage and memory totals of each subtree
+pub async fn system_process_tree(
+    req: HttpRequest,
+    query: web::Query<ProcessTreeQuery>,
+) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &query);
+
+    match features::system::process_tree(query.root) {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::NotFound()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
+#[api_v2_operation]
+/// Sends a signal to a process, or to all processes with a name, providing the result for each pid
+pub async fn system_process_signal(
+    req: HttpRequest,
+    json: web::Json<features::process::SignalRequest>,
+) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &json);
+
+    if let Err(response) = authorize(&req) {
+        return response;
+    }
+
+    match features::process::signal(&json.into_inner()) {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::BadRequest()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
+#[api_v2_operation]
+/// Provides the nice value, scheduling policy, priority and CPU affinity of a process
+pub async fn system_process_scheduling(req: HttpRequest, pid: web::Path<u32>) -> HttpResponse {
+    debug!("{} {}", req.method(), req.path());
+
+    let pid = pid.into_inner();
+    match features::process::scheduling(pid) {
+        Some(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        None => HttpResponse::NotFound()
+            .content_type("text/plain")
+            .body(format!("error: Process {} not found.", pid)),
+    }
+}
+
+#[api_v2_operation]
+/// Changes the nice value, scheduling policy, priority and CPU affinity of all threads of a process
+pub async fn set_system_process_scheduling(
+    req: HttpRequest,
+    pid: web::Path<u32>,
+    json: web::Json<features::process::SchedulingRequest>,
+) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &json);
+
+    if let Err(response) = authorize(&req) {
+        return response;
+    }
+
+    match features::process::set_scheduling(pid.into_inner(), &json.into_inner()) {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::BadRequest()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct ProcessDetailsQuery {
+    /// Provides the environment variables, with secret values redacted (default: false)
+    environment: Option<bool>,
+}
+
+#[api_v2_operation]
+/// Provides detailed information about a single process: threads, file descriptors, limits, memory and scheduling
+pub async fn system_process_pid(
+    req: HttpRequest,
+    pid: web::Path<u32>,
+    query: web::Query<ProcessDetailsQuery>,
+) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &query);
+
+    let pid = pid.into_inner();
+    match features::process::details(pid, query.environment.unwrap_or(false)) {
+        Some(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        None => HttpResponse::NotFound()
+            .content_type("text/plain")
+            .body(format!("error: Process {} not found.", pid)),
+    }
+}
+
+#[derive(Debug, Deserialize, Apiv2Schema)]
+pub struct SysctlQuery {
+    /// Key or subtree, like "vm.swappiness" or "net.core", all keys are provided if not defined
+    key: Option<String>,
+}
+
+#[api_v2_operation]
+/// Provides kernel parameters from /proc/sys
+pub async fn system_sysctl(req: HttpRequest, query: web::Query<SysctlQuery>) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &query);
+
+    match features::sysctl::sysctl(query.into_inner().key) {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::NotFound()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
+#[api_v2_operation]
+/// Changes a kernel parameter, if allowed by --sysctl-allowlist
+pub async fn set_system_sysctl(
+    req: HttpRequest,
+    json: web::Json<features::sysctl::SysctlRequest>,
+) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &json);
+
+    if let Err(response) = authorize(&req) {
+        return response;
+    }
 
-    Json(features::system::process())
+    match features::sysctl::set_sysctl(&json.into_inner()) {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::BadRequest()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
 }
 
 #[api_v2_operation]
 /// Provides system information for sensors only
 pub async fn system_temperature(req: HttpRequest) -> Json<Vec<features::system::Temperature>> {
-    debug!("{:#?}", req);
+    debug!("{} {}", req.method(), req.path());
 
     Json(features::system::temperature())
 }
 
+#[api_v2_operation]
+/// Provides current time, timezone, kernel clock synchronization status and RTC devices
+pub async fn system_time(req: HttpRequest) -> Json<features::time::Time> {
+    debug!("{} {}", req.method(), req.path());
+
+    Json(features::time::time())
+}
+
+#[api_v2_operation]
+/// Sets the system clock, optionally only above a minimum drift and also writing the hardware RTC
+pub async fn set_system_time(
+    req: HttpRequest,
+    json: web::Json<features::time::SetTimeRequest>,
+) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &json);
+
+    if let Err(response) = authorize(&req) {
+        return response;
+    }
+
+    match features::time::set_time(&json.into_inner()) {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::BadRequest()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
+#[api_v2_operation]
+/// Provides the current timezone and the available ones
+pub async fn system_time_timezone(req: HttpRequest) -> Json<features::time::Timezones> {
+    debug!("{} {}", req.method(), req.path());
+
+    Json(features::time::timezones())
+}
+
+#[api_v2_operation]
+/// Sets the system timezone, running services may need a restart to use it
+pub async fn set_system_time_timezone(
+    req: HttpRequest,
+    json: web::Json<features::time::TimezoneRequest>,
+) -> HttpResponse {
+    debug!("{} {}, {:#?}", req.method(), req.path(), &json);
+
+    if let Err(response) = authorize(&req) {
+        return response;
+    }
+
+    match features::time::set_timezone(&json.into_inner()) {
+        Ok(content) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(serde_json::to_string_pretty(&content).unwrap()),
+        Err(error) => HttpResponse::BadRequest()
+            .content_type("text/plain")
+            .body(format!("error: {}", error)),
+    }
+}
+
 #[api_v2_operation]
 /// Provides system information about current unix time
 pub async fn system_unix_time_seconds(req: HttpRequest) -> HttpResponse {
-    debug!("{:#?}", req);
+    debug!("{} {}", req.method(), req.path());
 
     HttpResponse::Ok()
         .content_type("text/plain")
@@ -160,7 +756,7 @@ pub async fn system_unix_time_seconds(req: HttpRequest) -> HttpResponse {
 #[api_v2_operation]
 /// (WIP) Provides information about all devices connected to the main computer
 pub fn udev(req: HttpRequest) -> HttpResponse {
-    debug!("{:#?}", req);
+    debug!("{} {}", req.method(), req.path());
 
     HttpResponse::Ok()
         .content_type("application/json")
@@ -170,7 +766,7 @@ pub fn udev(req: HttpRequest) -> HttpResponse {
 #[api_v2_operation]
 /// Provide platform specific information
 pub async fn platform(req: HttpRequest) -> HttpResponse {
-    debug!("{:#?}", req);
+    debug!("{} {}", req.method(), req.path());
 
     match features::platform::platform() {
         Ok(content) => HttpResponse::Ok()
@@ -185,7 +781,7 @@ pub async fn platform(req: HttpRequest) -> HttpResponse {
 #[api_v2_operation]
 /// Provide hardware model information
 pub async fn model(req: HttpRequest) -> HttpResponse {
-    debug!("{:#?}", req);
+    debug!("{} {}", req.method(), req.path());
 
     HttpResponse::Ok()
         .content_type("application/json")
@@ -193,7 +789,7 @@ pub async fn model(req: HttpRequest) -> HttpResponse {
 }
 
 pub fn websocket_kernel_buffer(req: HttpRequest, stream: web::Payload) -> HttpResponse {
-    debug!("{:#?}", req);
+    debug!("{} {}", req.method(), req.path());
 
     ws::start(
         features::kernel_websocket::new_websocket(
@@ -208,3 +804,20 @@ pub fn websocket_kernel_buffer(req: HttpRequest, stream: web::Payload) -> HttpRe
             .body(format!("error: {:#?}", error))
     })
 }
+
+pub fn websocket_power_supply(req: HttpRequest, stream: web::Payload) -> HttpResponse {
+    debug!("{} {}", req.method(), req.path());
+
+    ws::start(
+        features::kernel_websocket::new_websocket(
+            features::kernel_websocket::WebsocketEventType::PowerSupply,
+        ),
+        &req,
+        stream,
+    )
+    .unwrap_or_else(|error| {
+        HttpResponse::BadRequest()
+            .content_type("text/plain")
+            .body(format!("error: {:#?}", error))
+    })
+}